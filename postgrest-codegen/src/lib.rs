@@ -0,0 +1,551 @@
+//! # postgrest-codegen
+//!
+//! Generates typed Rust bindings for the tables, views and functions a
+//! PostgREST server exposes, from its [introspected](postgrest::introspect)
+//! OpenAPI document.
+//!
+//! For every relation, the generated code contains a module with the table
+//! name, column name constants, a `from` function starting a query, and
+//! `Row`, `Insert` and `Update` structs. Functions get an `Args` struct and a
+//! wrapper calling [`Postgrest::rpc`](postgrest::Postgrest::rpc). The
+//! generated code depends on `postgrest`, `serde` (with the `derive` feature)
+//! and `serde_json`.
+//!
+//! Use the `postgrest-codegen` binary, or call [`generate`] from a build
+//! script:
+//!
+//! ```
+//! use postgrest::introspect::ApiSchema;
+//! use postgrest_codegen::{generate, Options};
+//!
+//! let schema = ApiSchema::from_json(
+//!     r#"{
+//!         "paths": {"/users": {"get": {}, "post": {}}},
+//!         "definitions": {
+//!             "users": {
+//!                 "required": ["username"],
+//!                 "properties": {"username": {"format": "text", "type": "string"}}
+//!             }
+//!         }
+//!     }"#,
+//! )
+//! .unwrap();
+//! let code = generate(&schema, &Options::new());
+//! assert!(code.contains("pub const USERNAME: &str = \"username\";"));
+//! ```
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use postgrest::introspect::{ApiSchema, Argument, Column, Function, Relation};
+
+/// Options for [`generate`].
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    schema: Option<String>,
+}
+
+impl Options {
+    /// Creates the default options: the generated code uses the schema of
+    /// the client it's given.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pins the generated queries and calls to `schema`, regardless of the
+    /// client's schema.
+    pub fn schema<T>(mut self, schema: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.schema = Some(schema.into());
+        self
+    }
+}
+
+/// Generates the Rust source of the bindings for `schema`.
+pub fn generate(schema: &ApiSchema, options: &Options) -> String {
+    let mut out = String::from("// @generated by postgrest-codegen. Do not edit by hand.\n");
+    if let Some(name) = &options.schema {
+        out.push_str("\n/// The schema these bindings were generated for.\n");
+        writeln!(out, "pub const SCHEMA: &str = {:?};", name).unwrap();
+    }
+    let mut modules = Idents::default();
+    // Taken by the functions' module, even before there are any, so that
+    // adding one doesn't rename a table's module.
+    modules.0.insert("rpc".to_string());
+    for relation in &schema.relations {
+        out.push('\n');
+        relation_module(&mut out, relation, &modules.field(&relation.name), options);
+    }
+    if !schema.functions.is_empty() {
+        out.push('\n');
+        rpc_module(&mut out, &schema.functions, options);
+    }
+    out
+}
+
+fn relation_module(out: &mut String, relation: &Relation, module: &str, options: &Options) {
+    writeln!(out, "/// Bindings for `{}`.", relation.name).unwrap();
+    doc(out, "", relation.description.as_deref());
+    writeln!(out, "pub mod {} {{", module).unwrap();
+    out.push_str("    use postgrest::{Builder, Postgrest};\n\n");
+    writeln!(out, "    pub const TABLE: &str = {:?};\n", relation.name).unwrap();
+
+    let mut idents = Idents::default();
+    let fields: Vec<(String, &Column)> = relation
+        .columns
+        .iter()
+        .map(|column| (idents.field(&column.name), column))
+        .collect();
+
+    out.push_str("    /// Column names.\n    pub mod columns {\n");
+    for (field, column) in &fields {
+        writeln!(
+            out,
+            "        pub const {}: &str = {:?};",
+            constant(field),
+            column.name
+        )
+        .unwrap();
+    }
+    out.push_str("    }\n\n");
+
+    writeln!(out, "    /// Starts a query on `{}`.", relation.name).unwrap();
+    out.push_str("    pub fn from<C: Clone>(client: &Postgrest<C>) -> Builder<C> {\n");
+    if options.schema.is_some() {
+        out.push_str("        client.from_schema(super::SCHEMA, TABLE)\n");
+    } else {
+        out.push_str("        client.from(TABLE)\n");
+    }
+    out.push_str("    }\n\n");
+
+    writeln!(out, "    /// A row of `{}`.", relation.name).unwrap();
+    out.push_str("    #[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]\n");
+    out.push_str("    pub struct Row {\n");
+    let row_types: Vec<String> = fields
+        .iter()
+        .map(|(_, column)| {
+            let ty = column_type(column);
+            if column.nullable {
+                format!("Option<{}>", ty)
+            } else {
+                ty
+            }
+        })
+        .collect();
+    for ((field, column), ty) in fields.iter().zip(&row_types) {
+        field_def(out, field, column, ty, false);
+    }
+    out.push_str("    }\n\n");
+
+    out.push_str("    /// Typed column references, for filters and ordering.\n");
+    out.push_str("    #[allow(non_upper_case_globals)]\n    impl Row {\n");
+    for ((field, column), ty) in fields.iter().zip(&row_types) {
+        writeln!(
+            out,
+            "        pub const {}: postgrest::Column<Row, {}> = postgrest::Column::new({:?});",
+            field, ty, column.name
+        )
+        .unwrap();
+    }
+    out.push_str("    }\n");
+
+    if relation.insertable {
+        writeln!(
+            out,
+            "\n    /// A row to insert into `{}`. Columns that are nullable or have a\n    \
+             /// default are optional.",
+            relation.name
+        )
+        .unwrap();
+        out.push_str("    #[derive(Clone, Debug, PartialEq, serde::Serialize)]\n");
+        out.push_str("    pub struct Insert {\n");
+        for (field, column) in &fields {
+            let ty = column_type(column);
+            if column.nullable || column.default.is_some() {
+                field_def(out, field, column, &format!("Option<{}>", ty), true);
+            } else {
+                field_def(out, field, column, &ty, false);
+            }
+        }
+        out.push_str("    }\n");
+    }
+
+    if relation.updatable {
+        writeln!(
+            out,
+            "\n    /// Changes to apply to rows of `{}`. Unset fields are left unchanged;\n    \
+             /// set nullable fields to `Some(None)` to clear them.",
+            relation.name
+        )
+        .unwrap();
+        out.push_str("    #[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]\n");
+        out.push_str("    pub struct Update {\n");
+        for (field, column) in &fields {
+            let ty = column_type(column);
+            let ty = if column.nullable {
+                format!("Option<Option<{}>>", ty)
+            } else {
+                format!("Option<{}>", ty)
+            };
+            field_def(out, field, column, &ty, true);
+        }
+        out.push_str("    }\n");
+    }
+    out.push_str("}\n");
+}
+
+fn rpc_module(out: &mut String, functions: &[Function], options: &Options) {
+    out.push_str("/// Wrappers for the functions callable through `rpc`.\n");
+    out.push_str("pub mod rpc {\n");
+    out.push_str("    use postgrest::{Builder, Postgrest};\n");
+    let mut idents = Idents::default();
+    for function in functions {
+        let ident = idents.field(&function.name);
+        let name = if options.schema.is_some() {
+            format!(
+                "postgrest::QualifiedName::new(super::SCHEMA, {:?})",
+                function.name
+            )
+        } else {
+            format!("{:?}", function.name)
+        };
+        out.push('\n');
+        if function.arguments.is_empty() {
+            writeln!(out, "    /// Calls `{}`.", function.name).unwrap();
+            doc(out, "    ", function.description.as_deref());
+            writeln!(
+                out,
+                "    pub fn {}<C: Clone>(client: &Postgrest<C>) -> Builder<C> {{\n        \
+                 client.rpc({}, \"{{}}\")\n    }}",
+                ident, name
+            )
+            .unwrap();
+            continue;
+        }
+
+        let args = format!("{}Args", type_name(&function.name));
+        writeln!(out, "    /// Arguments of `{}`.", function.name).unwrap();
+        out.push_str("    #[derive(Clone, Debug, PartialEq, serde::Serialize)]\n");
+        writeln!(out, "    pub struct {} {{", args).unwrap();
+        let mut fields = Idents::default();
+        for argument in &function.arguments {
+            let field = fields.field(&argument.name);
+            let ty = argument_type(argument);
+            if argument.required {
+                field_line(out, &field, &argument.name, &ty, false);
+            } else {
+                field_line(
+                    out,
+                    &field,
+                    &argument.name,
+                    &format!("Option<{}>", ty),
+                    true,
+                );
+            }
+        }
+        out.push_str("    }\n\n");
+        writeln!(out, "    /// Calls `{}`.", function.name).unwrap();
+        doc(out, "    ", function.description.as_deref());
+        writeln!(
+            out,
+            "    pub fn {}<C: Clone>(client: &Postgrest<C>, args: &{}) -> Builder<C> {{\n        \
+             let params = serde_json::to_string(args).expect(\"arguments serialize to JSON\");\n        \
+             client.rpc({}, params)\n    }}",
+            ident, args, name
+        )
+        .unwrap();
+    }
+    out.push_str("}\n");
+}
+
+fn doc(out: &mut String, indent: &str, text: Option<&str>) {
+    if let Some(text) = text {
+        writeln!(out, "{}///", indent).unwrap();
+        for line in text.lines() {
+            writeln!(out, "{}/// {}", indent, line).unwrap();
+        }
+    }
+}
+
+fn field_def(out: &mut String, field: &str, column: &Column, ty: &str, skip_none: bool) {
+    if let Some(description) = &column.description {
+        for line in description.lines() {
+            writeln!(out, "        /// {}", line).unwrap();
+        }
+    }
+    field_line(out, field, &column.name, ty, skip_none);
+}
+
+fn field_line(out: &mut String, field: &str, name: &str, ty: &str, skip_none: bool) {
+    let mut attributes = Vec::new();
+    if field.trim_start_matches("r#") != name {
+        attributes.push(format!("rename = {:?}", name));
+    }
+    if skip_none {
+        attributes.push("skip_serializing_if = \"Option::is_none\"".to_string());
+    }
+    if !attributes.is_empty() {
+        writeln!(out, "        #[serde({})]", attributes.join(", ")).unwrap();
+    }
+    writeln!(out, "        pub {}: {},", field, ty).unwrap();
+}
+
+fn column_type(column: &Column) -> String {
+    rust_type(&column.data_type, column.json_type.as_deref())
+}
+
+fn argument_type(argument: &Argument) -> String {
+    rust_type(&argument.data_type, argument.json_type.as_deref())
+}
+
+/// Maps a PostgreSQL type to the Rust type its JSON representation
+/// deserializes into.
+fn rust_type(data_type: &str, json_type: Option<&str>) -> String {
+    if let Some(element) = data_type.strip_suffix("[]") {
+        return format!("Vec<{}>", rust_type(element, Some("string")));
+    }
+    let ty = match data_type {
+        "smallint" => "i16",
+        "integer" => "i32",
+        "bigint" => "i64",
+        "real" => "f32",
+        "double precision" => "f64",
+        "numeric" => "serde_json::Number",
+        "boolean" => "bool",
+        "json" | "jsonb" => "serde_json::Value",
+        _ => match json_type {
+            Some("string") => "String",
+            Some("integer") => "i64",
+            Some("number") => "f64",
+            Some("boolean") => "bool",
+            Some("array") => "Vec<serde_json::Value>",
+            _ => "serde_json::Value",
+        },
+    };
+    ty.to_string()
+}
+
+/// Hands out unique snake_case identifiers.
+#[derive(Default)]
+struct Idents(HashSet<String>);
+
+impl Idents {
+    fn field(&mut self, name: &str) -> String {
+        let base = snake_case(name);
+        let mut ident = base.clone();
+        let mut n = 2;
+        while !self.0.insert(ident.clone()) {
+            ident = format!("{}_{}", base, n);
+            n += 1;
+        }
+        match ident.as_str() {
+            "self" | "Self" | "super" | "crate" | "_" => format!("{}_", ident),
+            _ if KEYWORDS.contains(&ident.as_str()) => format!("r#{}", ident),
+            _ => ident,
+        }
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+fn snake_case(name: &str) -> String {
+    let mut ident = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            if prev_lower {
+                ident.push('_');
+            }
+            ident.push(c.to_ascii_lowercase());
+            prev_lower = false;
+        } else if c.is_ascii_alphanumeric() {
+            ident.push(c);
+            prev_lower = true;
+        } else if !ident.ends_with('_') {
+            ident.push('_');
+            prev_lower = false;
+        }
+    }
+    let ident = ident.trim_matches('_').to_string();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", ident)
+    } else {
+        ident
+    }
+}
+
+fn type_name(name: &str) -> String {
+    let name: String = snake_case(name)
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first).chain(chars).collect::<String>()
+        })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
+
+fn constant(field: &str) -> String {
+    field
+        .trim_start_matches("r#")
+        .trim_end_matches('_')
+        .to_ascii_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = r#"{
+        "paths": {
+            "/users": {"get": {}, "post": {}, "patch": {}, "delete": {}},
+            "/online_users": {"get": {}},
+            "/rpc/get_status": {
+                "post": {
+                    "parameters": [{
+                        "in": "body",
+                        "name": "args",
+                        "schema": {
+                            "required": ["name_param"],
+                            "properties": {
+                                "name_param": {"format": "text", "type": "string"},
+                                "verbose": {"format": "boolean", "type": "boolean"}
+                            }
+                        }
+                    }]
+                }
+            },
+            "/rpc/now": {"post": {}}
+        },
+        "definitions": {
+            "users": {
+                "required": ["username", "id"],
+                "properties": {
+                    "id": {"format": "bigint", "type": "integer", "default": "nextval('users_id_seq'::regclass)"},
+                    "username": {"format": "text", "type": "string", "description": "Login name"},
+                    "type": {"format": "text", "type": "string"},
+                    "lastSeen": {"format": "timestamp with time zone", "type": "string"},
+                    "tags": {"format": "text[]", "type": "array"},
+                    "data": {"format": "jsonb"}
+                }
+            },
+            "online_users": {
+                "properties": {"username": {"format": "text", "type": "string"}}
+            }
+        }
+    }"#;
+
+    fn generated(options: &Options) -> String {
+        let code = generate(&ApiSchema::from_json(DOC).unwrap(), options);
+        syn::parse_file(&code).unwrap_or_else(|err| panic!("{}\n{}", err, code));
+        code
+    }
+
+    #[test]
+    fn generates_relation_modules() {
+        let code = generated(&Options::new());
+        assert!(code.contains("pub mod users {"));
+        assert!(code.contains("pub const TABLE: &str = \"users\";"));
+        assert!(code.contains("pub const LAST_SEEN: &str = \"lastSeen\";"));
+        assert!(code.contains("pub const TYPE: &str = \"type\";"));
+        assert!(code.contains("client.from(TABLE)"));
+
+        let users = &code[code.find("pub mod users {").unwrap()..];
+        let row = &users[users.find("pub struct Row {").unwrap()..];
+        let row = &row[..row.find('}').unwrap()];
+        assert!(row.contains("pub id: i64,"));
+        assert!(row.contains("/// Login name\n        pub username: String,"));
+        assert!(row.contains("pub r#type: Option<String>,"));
+        assert!(
+            row.contains("#[serde(rename = \"lastSeen\")]\n        pub last_seen: Option<String>,")
+        );
+        assert!(row.contains("pub tags: Option<Vec<String>>,"));
+        assert!(row.contains("pub data: Option<serde_json::Value>,"));
+        assert!(users.contains(
+            "pub const last_seen: postgrest::Column<Row, Option<String>> = \
+             postgrest::Column::new(\"lastSeen\");"
+        ));
+
+        let insert = &users[users.find("pub struct Insert {").unwrap()..];
+        let insert = &insert[..insert.find('}').unwrap()];
+        assert!(insert.contains(
+            "#[serde(skip_serializing_if = \"Option::is_none\")]\n        pub id: Option<i64>,"
+        ));
+        assert!(insert.contains("pub username: String,"));
+
+        let update = &users[users.find("pub struct Update {").unwrap()..];
+        let update = &update[..update.find('}').unwrap()];
+        assert!(update.contains("pub username: Option<String>,"));
+        assert!(update.contains("pub tags: Option<Option<Vec<String>>>,"));
+    }
+
+    #[test]
+    fn read_only_relations_have_no_write_structs() {
+        let code = generated(&Options::new());
+        let online_users = &code[code.find("pub mod online_users {").unwrap()..];
+        let online_users = &online_users[..online_users.find("\n}\n").unwrap()];
+        assert!(online_users.contains("pub struct Row"));
+        assert!(!online_users.contains("pub struct Insert"));
+        assert!(!online_users.contains("pub struct Update"));
+    }
+
+    #[test]
+    fn generates_rpc_wrappers() {
+        let code = generated(&Options::new());
+        assert!(code.contains("pub struct GetStatusArgs {"));
+        assert!(code.contains("pub name_param: String,"));
+        assert!(code.contains("pub verbose: Option<bool>,"));
+        assert!(code.contains(
+            "pub fn get_status<C: Clone>(client: &Postgrest<C>, args: &GetStatusArgs) -> Builder<C>"
+        ));
+        assert!(code.contains("client.rpc(\"get_status\", params)"));
+        assert!(code.contains("client.rpc(\"now\", \"{}\")"));
+    }
+
+    #[test]
+    fn relation_named_rpc_keeps_clear_of_functions() {
+        let doc = r#"{
+            "paths": {"/rpc": {"get": {}}, "/rpc/now": {"post": {}}},
+            "definitions": {"rpc": {"properties": {"id": {"format": "integer", "type": "integer"}}}}
+        }"#;
+        let code = generate(&ApiSchema::from_json(doc).unwrap(), &Options::new());
+        syn::parse_file(&code).unwrap_or_else(|err| panic!("{}\n{}", err, code));
+        assert!(code.contains("pub mod rpc_2 {"));
+        assert!(code.contains("pub const TABLE: &str = \"rpc\";"));
+        assert_eq!(code.matches("pub mod rpc {").count(), 1);
+    }
+
+    #[test]
+    fn pins_schema() {
+        let code = generated(&Options::new().schema("personal"));
+        assert!(code.contains("pub const SCHEMA: &str = \"personal\";"));
+        assert!(code.contains("client.from_schema(super::SCHEMA, TABLE)"));
+        assert!(code.contains("postgrest::QualifiedName::new(super::SCHEMA, \"now\")"));
+    }
+
+    #[test]
+    fn sanitizes_identifiers() {
+        let mut idents = Idents::default();
+        assert_eq!(idents.field("user name"), "user_name");
+        assert_eq!(idents.field("user_name"), "user_name_2");
+        assert_eq!(idents.field("self"), "self_");
+        assert_eq!(idents.field("2fa"), "_2fa");
+        assert_eq!(idents.field("match"), "r#match");
+        assert_eq!(type_name("get_status"), "GetStatus");
+        assert_eq!(constant("self_"), "SELF");
+    }
+}
@@ -0,0 +1,114 @@
+use std::process::ExitCode;
+
+use postgrest::introspect::ApiSchema;
+use postgrest::Postgrest;
+use postgrest_codegen::{generate, Options};
+
+const USAGE: &str = "\
+Generates typed bindings for the tables and functions of a PostgREST API.
+
+Usage: postgrest-codegen [OPTIONS] <URL|FILE>
+
+Reads the OpenAPI document from a running PostgREST at URL, or from a saved
+FILE, and writes the bindings to stdout.
+
+Options:
+  -s, --schema <NAME>     Introspect NAME and pin the bindings to it
+  -H, --header <HEADER>   Send `Name: value` with the request, e.g. an apikey
+  -o, --output <FILE>     Write the bindings to FILE instead of stdout
+  -h, --help              Print this help";
+
+struct Args {
+    input: String,
+    schema: Option<String>,
+    headers: Vec<(String, String)>,
+    output: Option<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = std::env::args().skip(1);
+    let mut input = None;
+    let mut schema = None;
+    let mut headers = Vec::new();
+    let mut output = None;
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", name))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            "-s" | "--schema" => schema = Some(value(&arg)?),
+            "-o" | "--output" => output = Some(value(&arg)?),
+            "-H" | "--header" => {
+                let header = value(&arg)?;
+                let (name, value) = header
+                    .split_once(':')
+                    .ok_or_else(|| format!("invalid header {:?}", header))?;
+                headers.push((name.trim().to_string(), value.trim().to_string()));
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ if input.is_none() => input = Some(arg),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    Ok(Args {
+        input: input.ok_or("missing URL or FILE")?,
+        schema,
+        headers,
+        output,
+    })
+}
+
+async fn load(args: &Args) -> Result<ApiSchema, Box<dyn std::error::Error>> {
+    if !(args.input.starts_with("http://") || args.input.starts_with("https://")) {
+        let json = std::fs::read_to_string(&args.input)?;
+        return Ok(ApiSchema::from_json(&json)?);
+    }
+    let mut client = Postgrest::new(&args.input);
+    if let Some(schema) = &args.schema {
+        client = client.schema(schema);
+    }
+    for (name, value) in &args.headers {
+        let name = reqwest::header::HeaderName::from_bytes(name.as_bytes())?;
+        client = client.insert_header(name, value);
+    }
+    Ok(client.introspect().await?)
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    let schema = match load(&args).await {
+        Ok(schema) => schema,
+        Err(err) => {
+            eprintln!("error: failed to load {}: {}", args.input, err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut options = Options::new();
+    if let Some(schema) = &args.schema {
+        options = options.schema(schema);
+    }
+    let code = generate(&schema, &options);
+    match &args.output {
+        Some(path) => {
+            if let Err(err) = std::fs::write(path, code) {
+                eprintln!("error: failed to write {}: {}", path, err);
+                return ExitCode::FAILURE;
+            }
+        }
+        None => print!("{}", code),
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,275 @@
+//! Derive macro for `postgrest::Table`. Use it through the `derive` feature
+//! of `postgrest`, which re-exports it as `postgrest::PostgrestTable`.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{
+    meta::ParseNestedMeta, parenthesized, parse_macro_input, token, Attribute, Data, DeriveInput,
+    Error, Expr, Fields, LitStr, Result, Token,
+};
+
+/// Implements `postgrest::Table` for a struct with named fields, and adds a
+/// typed `postgrest::Column` constant per field, e.g. `User::username`.
+///
+/// Container attributes, all optional:
+///
+/// - `#[postgrest(table = "users")]` sets the relation name, which defaults
+///   to the struct name in snake_case.
+/// - `#[postgrest(schema = "personal")]` sets the schema.
+/// - `#[serde(rename_all = "camelCase")]` renames the columns as serde
+///   renames the fields.
+///
+/// Field attributes:
+///
+/// - `#[postgrest(primary_key)]` marks a primary key column.
+/// - `#[postgrest(rename = "name")]` sets the column name. `#[serde(rename)]`
+///   is honored too.
+/// - `#[postgrest(skip)]` leaves the field out of the columns, as does
+///   `#[serde(skip)]`.
+///
+/// `#[serde(flatten)]` fields aren't supported, since their columns can't be
+/// listed:
+///
+/// ```compile_fail
+/// use postgrest::PostgrestTable;
+/// use serde::Deserialize;
+///
+/// #[derive(PostgrestTable, Deserialize)]
+/// struct User {
+///     username: String,
+///     #[serde(flatten)]
+///     profile: Profile,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Profile {
+///     bio: String,
+/// }
+/// ```
+#[proc_macro_derive(PostgrestTable, attributes(postgrest))]
+pub fn derive_table(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let mut table = None;
+    let mut schema = None;
+    for attr in postgrest_attrs(&input.attrs) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                table = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("schema") {
+                schema = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else {
+                return Err(meta.error("expected `table` or `schema`"));
+            }
+            Ok(())
+        })?;
+    }
+    let table = table.unwrap_or_else(|| snake_case(&input.ident.to_string()));
+
+    let mut rename_all = None;
+    for attr in serde_attrs(&input.attrs) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("rename_all") {
+                return skip_value(&meta);
+            }
+            if meta.input.peek(Token![=]) {
+                rename_all = Some(meta.value()?.parse::<LitStr>()?);
+                return Ok(());
+            }
+            // `rename_all(serialize = "...", deserialize = "...")`: rows
+            // are read, so deserialization names the columns.
+            meta.parse_nested_meta(|meta| {
+                let rule = meta.value()?.parse::<LitStr>()?;
+                if meta.path.is_ident("deserialize") {
+                    rename_all = Some(rule);
+                }
+                Ok(())
+            })
+        });
+    }
+    if let Some(rule) = &rename_all {
+        if rename(&rule.value(), "").is_none() {
+            return Err(Error::new_spanned(rule, "unknown `rename_all` rule"));
+        }
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "PostgrestTable requires named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "PostgrestTable can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut columns = Vec::new();
+    let mut primary_key = Vec::new();
+    let mut handles = Vec::new();
+    for field in fields {
+        let mut name = field
+            .ident
+            .as_ref()
+            .map(|ident| ident.to_string().trim_start_matches("r#").to_string())
+            .unwrap_or_default();
+        if let Some(rule) = &rename_all {
+            name = rename(&rule.value(), &name).unwrap_or(name);
+        }
+        let mut skip = false;
+        let mut is_key = false;
+        let mut flatten = None;
+        for attr in serde_attrs(&field.attrs) {
+            // Other serde options are none of our business, and our own
+            // attributes below take precedence.
+            let _ = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    skip = true;
+                } else if meta.path.is_ident("flatten") {
+                    flatten = Some(meta.path.clone());
+                } else if meta.path.is_ident("rename") && meta.input.peek(Token![=]) {
+                    name = meta.value()?.parse::<LitStr>()?.value();
+                } else {
+                    skip_value(&meta)?;
+                }
+                Ok(())
+            });
+        }
+        for attr in postgrest_attrs(&field.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("primary_key") {
+                    is_key = true;
+                } else if meta.path.is_ident("skip") {
+                    skip = true;
+                } else if meta.path.is_ident("rename") {
+                    name = meta.value()?.parse::<LitStr>()?.value();
+                } else {
+                    return Err(meta.error("expected `primary_key`, `rename` or `skip`"));
+                }
+                Ok(())
+            })?;
+        }
+        if skip {
+            continue;
+        }
+        if let Some(flatten) = flatten {
+            return Err(Error::new_spanned(
+                flatten,
+                "PostgrestTable can't list the columns of a `#[serde(flatten)]` field",
+            ));
+        }
+        if is_key {
+            primary_key.push(name.clone());
+        }
+        let (vis, field_ident, ty) = (&field.vis, &field.ident, &field.ty);
+        handles.push(quote! {
+            #vis const #field_ident: ::postgrest::Column<Self, #ty> = ::postgrest::Column::new(#name);
+        });
+        columns.push(name);
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let schema = match schema {
+        Some(schema) => quote!(::core::option::Option::Some(#schema)),
+        None => quote!(::core::option::Option::None),
+    };
+    Ok(quote! {
+        impl #impl_generics ::postgrest::Table for #ident #ty_generics #where_clause {
+            const NAME: &'static str = #table;
+            const SCHEMA: ::core::option::Option<&'static str> = #schema;
+            const PRIMARY_KEY: &'static [&'static str] = &[#(#primary_key),*];
+            const COLUMNS: &'static [&'static str] = &[#(#columns),*];
+        }
+
+        #[allow(non_upper_case_globals)]
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #(#handles)*
+        }
+    })
+}
+
+fn postgrest_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("postgrest"))
+}
+
+fn serde_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("serde"))
+}
+
+/// Consumes the value of a serde option we don't read.
+fn skip_value(meta: &ParseNestedMeta) -> Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<Expr>()?;
+    } else if meta.input.peek(token::Paren) {
+        let _content;
+        parenthesized!(_content in meta.input);
+    }
+    Ok(())
+}
+
+/// Renames a snake_case `field` like serde's `rename_all = rule`, or `None`
+/// for an unknown rule.
+fn rename(rule: &str, field: &str) -> Option<String> {
+    let pascal = || {
+        let mut pascal = String::new();
+        let mut capitalize = true;
+        for c in field.chars() {
+            if c == '_' {
+                capitalize = true;
+            } else if capitalize {
+                pascal.push(c.to_ascii_uppercase());
+                capitalize = false;
+            } else {
+                pascal.push(c);
+            }
+        }
+        pascal
+    };
+    Some(match rule {
+        "lowercase" => field.to_ascii_lowercase(),
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => field.to_ascii_uppercase(),
+        "PascalCase" => pascal(),
+        "camelCase" => {
+            let pascal = pascal();
+            let mut chars = pascal.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+                None => pascal,
+            }
+        }
+        "snake_case" => field.to_string(),
+        "kebab-case" => field.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => field.to_ascii_uppercase().replace('_', "-"),
+        _ => return None,
+    })
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
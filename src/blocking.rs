@@ -0,0 +1,511 @@
+//! A blocking PostgREST client, for code that doesn't run on an async
+//! runtime.
+//!
+//! The API mirrors the async one: [`Postgrest`] creates a [`Builder`] that
+//! supports the same filters and modifiers, and `execute()` blocks the current
+//! thread until the response arrives.
+//!
+//! # Example
+//!
+//! ```
+//! use postgrest::blocking::Postgrest;
+//!
+//! # fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = Postgrest::new("https://your.postgrest.endpoint");
+//! let resp = client
+//!     .from("countries")
+//!     .eq("name", "Germany")
+//!     .select("*")
+//!     .execute()?;
+//! let body = resp.text()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Note
+//!
+//! Like `reqwest::blocking`, this client must not be used from within an
+//! async runtime.
+
+use std::collections::VecDeque;
+use std::io::Read;
+use std::sync::Mutex;
+
+use bytes::Bytes;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::IntoHeaderName;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::bulk::{self, Chunks};
+use crate::explain::Explain;
+use crate::introspect::ApiSchema;
+use crate::stream::{CsvDecoder, Decoder, JsonArrayDecoder};
+use crate::{
+    BulkOptions, BulkReport, ChunkReport, Error, ExplainOptions, Middleware, Pagination,
+    RelationName, RequestParts, RetryPolicy, Table,
+};
+
+/// QueryBuilder for the blocking client. All filters and modifiers of the
+/// async [`Builder`](crate::Builder) are available.
+pub type Builder = crate::Builder<Client>;
+
+/// A blocking PostgREST client.
+#[derive(Clone, Debug)]
+pub struct Postgrest(crate::Postgrest<Client>);
+
+impl Postgrest {
+    /// Creates a blocking Postgrest client.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::blocking::Postgrest;
+    ///
+    /// let client = Postgrest::new("http://your.postgrest.endpoint");
+    /// ```
+    pub fn new<T>(url: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Postgrest(crate::Postgrest::with_client(url, Client::new()))
+    }
+
+    /// Switches the schema. See [`crate::Postgrest::schema`].
+    pub fn schema<T>(self, schema: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Postgrest(self.0.schema(schema))
+    }
+
+    /// Add arbitrary headers to the request. See
+    /// [`crate::Postgrest::insert_header`].
+    pub fn insert_header(
+        self,
+        header_name: impl IntoHeaderName,
+        header_value: impl AsRef<str>,
+    ) -> Self {
+        Postgrest(self.0.insert_header(header_name, header_value))
+    }
+
+    /// Retries transient failures according to `policy`. See
+    /// [`crate::Postgrest::retry`].
+    pub fn retry(self, policy: RetryPolicy) -> Self {
+        Postgrest(self.0.retry(policy))
+    }
+
+    /// Chooses how limits and offsets are sent. See
+    /// [`crate::Postgrest::pagination`].
+    pub fn pagination(self, pagination: Pagination) -> Self {
+        Postgrest(self.0.pagination(pagination))
+    }
+
+    /// Registers `middleware` to run around every request. See
+    /// [`crate::Postgrest::middleware`].
+    pub fn middleware<M>(self, middleware: M) -> Self
+    where
+        M: Middleware + 'static,
+    {
+        Postgrest(self.0.middleware(middleware))
+    }
+
+    /// Redacts filter values in tracing spans. See
+    /// [`crate::Postgrest::redact_query_values`].
+    #[cfg(feature = "tracing")]
+    pub fn redact_query_values(self) -> Self {
+        Postgrest(self.0.redact_query_values())
+    }
+
+    /// Fetches the tables, views and functions of the current schema. See
+    /// [`crate::Postgrest::introspect`].
+    pub fn introspect(&self) -> Result<ApiSchema, Error> {
+        let resp = self.0.openapi_request().execute()?;
+        let status = resp.status();
+        let body = resp.text()?;
+        if !status.is_success() {
+            return Err(Error::Status { status, body });
+        }
+        Ok(ApiSchema::from_json(&body)?)
+    }
+
+    /// Perform a table operation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::blocking::Postgrest;
+    ///
+    /// let client = Postgrest::new("http://your.postgrest.endpoint");
+    /// client.from("table");
+    /// ```
+    pub fn from<T>(&self, table: T) -> Builder
+    where
+        T: RelationName,
+    {
+        self.0.from(table)
+    }
+
+    /// Perform a table operation on the relation modelled by `T`. See
+    /// [`crate::Postgrest::table`].
+    pub fn table<T>(&self) -> Builder
+    where
+        T: Table,
+    {
+        self.0.table::<T>()
+    }
+
+    /// Perform a table operation on `table` in `schema`. See
+    /// [`crate::Postgrest::from_schema`].
+    pub fn from_schema<S, T>(&self, schema: S, table: T) -> Builder
+    where
+        S: Into<String>,
+        T: AsRef<str>,
+    {
+        self.0.from_schema(schema, table)
+    }
+
+    /// Inserts `rows` into `table` in chunks, on up to
+    /// [`concurrency`](BulkOptions::concurrency) threads at a time. See
+    /// [`crate::Postgrest::bulk_insert`].
+    pub fn bulk_insert<N, I>(&self, table: N, rows: I, options: BulkOptions) -> BulkReport
+    where
+        N: RelationName,
+        I: IntoIterator,
+        I::IntoIter: Send,
+        I::Item: Serialize,
+    {
+        let table = table.into_qualified_name();
+        let policy = bulk::retry_policy(&options, self.0.retry.as_ref());
+        let chunks = Mutex::new(Chunks::new(rows.into_iter(), &options));
+        let reports = Mutex::new(Vec::new());
+        std::thread::scope(|scope| {
+            for _ in 0..options.concurrency {
+                scope.spawn(|| loop {
+                    let chunk = match chunks.lock().unwrap().next() {
+                        Some(chunk) => chunk,
+                        None => break,
+                    };
+                    let request = chunk
+                        .body
+                        .map(|body| bulk::chunk_request(&self.0, table.clone(), body, &options));
+                    let report = send_chunk(chunk.index, chunk.rows, request, policy.as_ref());
+                    reports.lock().unwrap().push(report);
+                });
+            }
+        });
+        let mut chunks = reports.into_inner().unwrap();
+        chunks.sort_by_key(|chunk| chunk.index);
+        BulkReport { chunks }
+    }
+
+    /// Perform a stored procedure call.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::blocking::Postgrest;
+    ///
+    /// let client = Postgrest::new("http://your.postgrest.endpoint");
+    /// client.rpc("multiply", r#"{"a": 1, "b": 2}"#);
+    /// ```
+    pub fn rpc<T, U>(&self, function: T, params: U) -> Builder
+    where
+        T: RelationName,
+        U: Into<String>,
+    {
+        self.0.rpc(function, params)
+    }
+}
+
+impl Builder {
+    /// Build the PostgREST request.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the request is invalid. See [`crate::Builder::build`].
+    pub fn build(self) -> RequestBuilder {
+        match self.try_build() {
+            Ok(request) => request,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Build the PostgREST request, failing like `execute()` would for an
+    /// invalid request. See [`crate::Builder::try_build`].
+    pub fn try_build(self) -> Result<RequestBuilder, Error> {
+        self.validate()?;
+        Ok(self.to_request_parts().into_blocking_request(self.client()))
+    }
+
+    /// Executes the PostgREST request, blocking until the response arrives.
+    pub fn execute(self) -> Result<Response, Error> {
+        let call = self.into_call()?;
+        #[cfg(feature = "tracing")]
+        let _span = call.span.enter();
+        let result = send(&call.client, call.parts.clone(), call.retry.clone());
+        match &result {
+            Ok(resp) => call.on_response(resp.status(), resp.headers()),
+            Err(err) => call.on_error(err),
+        }
+        result
+    }
+
+    /// Checks whether any row matches the filters. See
+    /// [`crate::Builder::exists`].
+    pub fn exists(self) -> Result<bool, Error> {
+        let resp = self.array().head().limit(1).execute()?;
+        crate::builder::rows_exist(resp.status(), resp.headers())
+    }
+
+    /// Executes the request and deserializes its only row, if any. See
+    /// [`crate::Builder::maybe_single`].
+    pub fn maybe_single<R>(self) -> Result<Option<R>, Error>
+    where
+        R: DeserializeOwned,
+    {
+        let mut rows = self.at_most_two().execute_iter::<R>()?;
+        let row = rows.next().transpose()?;
+        if row.is_some() && rows.next().transpose()?.is_some() {
+            return Err(Error::MultipleRows);
+        }
+        Ok(row)
+    }
+
+    /// Executes the request and deserializes its only row. See
+    /// [`crate::Builder::execute_single`].
+    pub fn execute_single<R>(self) -> Result<R, Error>
+    where
+        R: DeserializeOwned,
+    {
+        self.maybe_single()?.ok_or(Error::NoRows)
+    }
+
+    /// Returns the query plan of this request. See
+    /// [`crate::Builder::explain`].
+    pub fn explain(self, options: ExplainOptions) -> Result<Explain, Error> {
+        let body = self.plan("json", &options).execute_text()?;
+        Ok(Explain::from_json(&body)?)
+    }
+
+    /// Returns the query plan of this request in PostgreSQL's text format.
+    /// See [`crate::Builder::explain_text`].
+    pub fn explain_text(self, options: ExplainOptions) -> Result<String, Error> {
+        self.plan("text", &options).execute_text()
+    }
+
+    /// Executes the request as GeoJSON and parses the features. See
+    /// [`crate::Builder::execute_geojson`].
+    #[cfg(feature = "geo")]
+    pub fn execute_geojson(self) -> Result<geojson::FeatureCollection, Error> {
+        let body = self.geojson().execute_text()?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Executes the request and returns the raw body. See
+    /// [`crate::Builder::execute_bytes`].
+    pub fn execute_bytes(self) -> Result<Bytes, Error> {
+        Ok(self.execute_ok()?.bytes()?)
+    }
+
+    /// Executes the request and returns a reader over the raw body, so
+    /// large downloads don't have to fit in memory.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::blocking::Postgrest;
+    ///
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let mut body = client
+    ///     .rpc("export", "{}")
+    ///     .accept("application/octet-stream")
+    ///     .execute_reader()?;
+    /// let mut file = std::fs::File::create("export.bin")?;
+    /// std::io::copy(&mut body, &mut file)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn execute_reader(self) -> Result<impl Read, Error> {
+        self.execute_ok()
+    }
+
+    /// Executes the request and yields the rows of the result one by one as
+    /// they arrive. See [`crate::Builder::execute_stream`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::blocking::Postgrest;
+    ///
+    /// # fn run() -> Result<(), postgrest::Error> {
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// for row in client.from("messages").execute_iter::<serde_json::Value>()? {
+    ///     println!("{}", row?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn execute_iter<R>(self) -> Result<impl Iterator<Item = Result<R, Error>>, Error>
+    where
+        R: DeserializeOwned,
+    {
+        let body = self.array().execute_ok()?;
+        Ok(Decoded::new(body, JsonArrayDecoder::new()))
+    }
+
+    /// Executes the request as CSV and yields its records one by one, the
+    /// header first. See [`crate::Builder::execute_csv_stream`].
+    pub fn execute_csv_iter(
+        self,
+    ) -> Result<impl Iterator<Item = Result<Vec<String>, Error>>, Error> {
+        let body = self.accept("text/csv").execute_ok()?;
+        Ok(Decoded::new(body, CsvDecoder::default()))
+    }
+
+    fn execute_text(self) -> Result<String, Error> {
+        Ok(self.execute_ok()?.text()?)
+    }
+
+    fn execute_ok(self) -> Result<Response, Error> {
+        let resp = self.execute()?;
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text()?;
+            return Err(Error::Status { status, body });
+        }
+        Ok(resp)
+    }
+}
+
+fn send_chunk(
+    index: usize,
+    rows: usize,
+    request: Result<Builder, serde_json::Error>,
+    policy: Option<&RetryPolicy>,
+) -> ChunkReport {
+    let mut report = ChunkReport {
+        index,
+        rows,
+        attempts: 0,
+        result: Ok(()),
+    };
+    let request = match request {
+        Ok(request) => request,
+        Err(err) => {
+            report.result = Err(Error::Encode(err));
+            return report;
+        }
+    };
+    loop {
+        report.attempts += 1;
+        let result = request.clone().execute();
+        let outcome = result.as_ref().map(|resp| (resp.status(), resp.headers()));
+        match policy.and_then(|policy| policy.next_delay(report.attempts, outcome)) {
+            Some(delay) => std::thread::sleep(delay),
+            None => {
+                report.result = result.and_then(|resp| {
+                    let status = resp.status();
+                    if status.is_success() {
+                        return Ok(());
+                    }
+                    let body = resp.text()?;
+                    Err(Error::Status { status, body })
+                });
+                return report;
+            }
+        }
+    }
+}
+
+/// Decodes a response body with `D` as it is read.
+struct Decoded<D: Decoder> {
+    body: Option<Response>,
+    decoder: D,
+    items: VecDeque<D::Item>,
+}
+
+impl<D: Decoder> Decoded<D> {
+    fn new(body: Response, decoder: D) -> Self {
+        Decoded {
+            body: Some(body),
+            decoder,
+            items: VecDeque::new(),
+        }
+    }
+}
+
+impl<D: Decoder> Iterator for Decoded<D> {
+    type Item = Result<D::Item, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buf = [0; 8192];
+        loop {
+            if let Some(item) = self.items.pop_front() {
+                return Some(Ok(item));
+            }
+            let body = self.body.as_mut()?;
+            let result = match body.read(&mut buf) {
+                Ok(0) => {
+                    self.body = None;
+                    self.decoder.finish(&mut self.items)
+                }
+                Ok(n) => self.decoder.push(&buf[..n], &mut self.items),
+                Err(err) => Err(Error::Transport(Box::new(err))),
+            };
+            if let Err(err) = result {
+                self.body = None;
+                self.items.clear();
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+fn send(
+    client: &Client,
+    parts: RequestParts,
+    policy: Option<RetryPolicy>,
+) -> Result<Response, Error> {
+    let request = parts.into_blocking_request(client).build()?;
+    let policy = match policy {
+        Some(policy) => policy,
+        None => return Ok(client.execute(request)?),
+    };
+
+    let mut attempt = 1;
+    loop {
+        // The body is always an in-memory string, so this can't fail.
+        let retry = request.try_clone().expect("request should be clonable");
+        let result = client.execute(retry).map_err(Error::from);
+        let outcome = result.as_ref().map(|resp| (resp.status(), resp.headers()));
+        match policy.next_delay(attempt, outcome) {
+            Some(delay) => std::thread::sleep(delay),
+            None => return result,
+        }
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REST_URL: &str = "http://localhost:3000";
+
+    #[test]
+    fn builds_same_request_as_async_client() {
+        let blocking = Postgrest::new(REST_URL)
+            .schema("personal")
+            .from("users")
+            .eq("username", "supabot")
+            .select("status");
+        let non_blocking = crate::Postgrest::new(REST_URL)
+            .schema("personal")
+            .from("users")
+            .eq("username", "supabot")
+            .select("status");
+        assert_eq!(blocking.to_request_parts(), non_blocking.to_request_parts());
+    }
+}
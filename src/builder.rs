@@ -1,12 +1,34 @@
-pub use reqwest::Error;
-use reqwest::{
+use crate::explain::Explain;
+use crate::stream::{self, CsvDecoder, JsonArrayDecoder};
+use crate::{
+    Client, Direction, Error, ExplainOptions, Middleware, Middlewares, Order, RequestParts,
+    Response, RetryPolicy, Transport,
+};
+use bytes::Bytes;
+use futures_util::{Stream, TryStreamExt};
+use http::{
     header::{HeaderMap, HeaderValue},
-    Client, Method, Response,
+    Method, StatusCode,
 };
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::sync::Arc;
+
+/// How [`Builder::limit`], [`Builder::offset`] and [`Builder::range`] are
+/// sent to PostgREST.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pagination {
+    /// A `Range` header, e.g. `Range: 10-19`.
+    #[default]
+    Range,
+    /// `limit` and `offset` query parameters, e.g. `?limit=10&offset=10`.
+    /// These survive proxies that strip the `Range` header.
+    Query,
+}
 
 /// QueryBuilder struct
 #[derive(Clone, Debug)]
-pub struct Builder {
+pub struct Builder<C = Client> {
     method: Method,
     url: String,
     schema: Option<String>,
@@ -15,15 +37,29 @@ pub struct Builder {
     headers: HeaderMap,
     body: Option<String>,
     is_rpc: bool,
+    pagination: Pagination,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    invalid_range: Option<(usize, usize)>,
+    single: bool,
+    strip_nulls: bool,
+    geojson: bool,
+    media_type: Option<String>,
+    // The `select` parameter was set by `Postgrest::table`.
+    table_select: bool,
+    retry: Option<RetryPolicy>,
+    idempotent: bool,
+    pub(crate) middlewares: Middlewares,
+    #[cfg(feature = "tracing")]
+    redact_query_values: bool,
     // sharing a client is a good idea, performance wise
     // the client has to live at least as much as the builder
-    client: Client,
+    client: C,
 }
 
-// TODO: Test Unicode support
-impl Builder {
+impl<C> Builder<C> {
     /// Creates a new `Builder` with the specified `schema`.
-    pub fn new<T>(url: T, schema: Option<String>, headers: HeaderMap, client: Client) -> Self
+    pub fn new<T>(url: T, schema: Option<String>, headers: HeaderMap, client: C) -> Self
     where
         T: Into<String>,
     {
@@ -37,6 +73,20 @@ impl Builder {
             headers,
             body: None,
             is_rpc: false,
+            pagination: Pagination::default(),
+            limit: None,
+            offset: None,
+            invalid_range: None,
+            single: false,
+            strip_nulls: false,
+            geojson: false,
+            media_type: None,
+            table_select: false,
+            retry: None,
+            idempotent: false,
+            middlewares: Middlewares::default(),
+            #[cfg(feature = "tracing")]
+            redact_query_values: false,
             client,
         };
         builder
@@ -68,12 +118,37 @@ impl Builder {
         self
     }
 
+    /// Switches the schema for this request only, overriding the one set on
+    /// the client.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::Postgrest;
+    ///
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let parts = client
+    ///     .from("users")
+    ///     .schema("personal")
+    ///     .select("*")
+    ///     .to_request_parts();
+    /// assert_eq!(parts.headers["Accept-Profile"], "personal");
+    /// ```
+    pub fn schema<T>(mut self, schema: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.schema = Some(schema.into());
+        self
+    }
+
     /// Performs horizontal filtering with SELECT.
     ///
     /// # Note
     ///
     /// `columns` is whitespace-sensitive, so you need to omit them unless your
-    /// column name contains whitespaces.
+    /// column name contains whitespaces. `select` replaces the columns chosen
+    /// by [`Postgrest::table`](crate::Postgrest::table).
     ///
     /// # Example
     ///
@@ -157,28 +232,50 @@ impl Builder {
     where
         T: Into<String>,
     {
+        if std::mem::take(&mut self.table_select) {
+            self.queries.retain(|(key, _)| key != "select");
+        }
         self.queries.push(("select".to_string(), columns.into()));
         self
     }
 
-    /// Orders the result with the specified `columns`.
+    /// Selects the columns of a [`Table`](crate::Table), which a later
+    /// [`select`](Self::select) replaces.
+    pub(crate) fn table_select(mut self, columns: String) -> Self {
+        self = self.select(columns);
+        self.table_select = true;
+        self
+    }
+
+    /// Orders the result by `order`. Calling `order` again adds another
+    /// term to the same `order` parameter, so earlier terms take precedence.
     ///
     /// # Example
     ///
     /// ```
-    /// use postgrest::Postgrest;
+    /// use postgrest::{Order, Postgrest};
     ///
     /// let client = Postgrest::new("https://your.postgrest.endpoint");
     /// client
     ///     .from("users")
     ///     .select("*")
-    ///     .order("username.desc.nullsfirst,age_range");
+    ///     .order(Order::desc("username").nulls_first())
+    ///     .order("age_range");
     /// ```
-    pub fn order<T>(mut self, columns: T) -> Self
+    pub fn order<T>(mut self, order: T) -> Self
     where
-        T: Into<String>,
+        T: Into<Order>,
     {
-        self.queries.push(("order".to_string(), columns.into()));
+        let order = order.into();
+        let key = order.key();
+        let term = order.to_string();
+        match self.queries.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, value)) => {
+                value.push(',');
+                value.push_str(&term);
+            }
+            None => self.queries.push((key, term)),
+        }
         self
     }
 
@@ -187,6 +284,7 @@ impl Builder {
     /// # Example
     ///
     /// ```
+    /// # #![allow(deprecated)]
     /// use postgrest::Postgrest;
     ///
     /// let client = Postgrest::new("https://your.postgrest.endpoint");
@@ -195,8 +293,12 @@ impl Builder {
     ///     .select("name, cities(name)")
     ///     .order_with_options("name", Some("cities"), true, false);
     /// ```
+    #[deprecated(
+        since = "1.7.0",
+        note = "use `order(Order::asc(column).referenced_table(table))` instead"
+    )]
     pub fn order_with_options<T, U>(
-        mut self,
+        self,
         columns: T,
         foreign_table: Option<U>,
         ascending: bool,
@@ -206,53 +308,54 @@ impl Builder {
         T: Into<String>,
         U: Into<String>,
     {
-        let mut key = "order".to_string();
+        let direction = if ascending {
+            Direction::Asc
+        } else {
+            Direction::Desc
+        };
+        let mut order = Order::new(columns.into()).direction(direction);
+        order = if nulls_first {
+            order.nulls_first()
+        } else {
+            order.nulls_last()
+        };
         if let Some(foreign_table) = foreign_table {
-            let foreign_table = foreign_table.into();
-            if !foreign_table.is_empty() {
-                key = format!("{}.order", foreign_table);
-            }
-        }
-
-        let mut ascending_string = "desc";
-        if ascending {
-            ascending_string = "asc";
-        }
-
-        let mut nulls_first_string = "nullslast";
-        if nulls_first {
-            nulls_first_string = "nullsfirst";
+            order = order.referenced_table(foreign_table);
         }
+        self.order(order)
+    }
 
-        let existing_order = self.queries.iter().find(|(k, _)| k == &key);
-        match existing_order {
-            Some((_, v)) => {
-                let new_order = format!(
-                    "{},{}.{}.{}",
-                    v,
-                    columns.into(),
-                    ascending_string,
-                    nulls_first_string
-                );
-                self.queries.push((key, new_order));
-            }
-            None => {
-                self.queries.push((
-                    key,
-                    format!(
-                        "{}.{}.{}",
-                        columns.into(),
-                        ascending_string,
-                        nulls_first_string
-                    ),
-                ));
-            }
-        }
+    /// Chooses how limits and offsets are sent for this request only,
+    /// overriding the mode set on the client with
+    /// [`Postgrest::pagination`](crate::Postgrest::pagination).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::{Pagination, Postgrest};
+    ///
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let parts = client
+    ///     .from("users")
+    ///     .select("*")
+    ///     .pagination(Pagination::Query)
+    ///     .limit(20)
+    ///     .to_request_parts();
+    /// assert!(parts.url.ends_with("select=*&limit=20"));
+    /// ```
+    pub fn pagination(mut self, pagination: Pagination) -> Self {
+        self.pagination = pagination;
+        self.paginate();
         self
     }
 
     /// Limits the result with the specified `count`.
     ///
+    /// # Note
+    ///
+    /// A `Range` header can't ask for zero rows, so `limit(0)` is always sent
+    /// as `limit=0`, which returns no rows but still reports counts.
+    ///
     /// # Example
     ///
     /// ```
@@ -265,12 +368,29 @@ impl Builder {
     ///     .limit(20);
     /// ```
     pub fn limit(mut self, count: usize) -> Self {
-        self.headers
-            .insert("Range-Unit", HeaderValue::from_static("items"));
-        self.headers.insert(
-            "Range",
-            HeaderValue::from_str(&format!("0-{}", count - 1)).unwrap(),
-        );
+        self.limit = Some(count);
+        self.paginate();
+        self
+    }
+
+    /// Skips the first `count` rows of the result.
+    ///
+    /// # Example
+    ///
+    /// This retrieves the 11th to 30th entries in the result:
+    /// ```
+    /// use postgrest::Postgrest;
+    ///
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// client
+    ///     .from("users")
+    ///     .select("*")
+    ///     .offset(10)
+    ///     .limit(20);
+    /// ```
+    pub fn offset(mut self, count: usize) -> Self {
+        self.offset = Some(count);
+        self.paginate();
         self
     }
 
@@ -296,8 +416,40 @@ impl Builder {
         self
     }
 
+    /// Skips the first `count` rows of a foreign table.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::Postgrest;
+    ///
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// client
+    ///     .from("countries")
+    ///     .select("name, cities(name)")
+    ///     .foreign_table_limit(10, "cities")
+    ///     .foreign_table_offset(10, "cities");
+    /// ```
+    pub fn foreign_table_offset<T>(mut self, count: usize, foreign_table: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.queries.push((
+            format!("{}.offset", foreign_table.into()),
+            count.to_string(),
+        ));
+        self
+    }
+
     /// Limits the result to rows within the specified range, inclusive.
     ///
+    /// # Note
+    ///
+    /// If `high` is below `low`, executing the request fails with
+    /// [`Error::InvalidRange`] without sending it, and
+    /// [`to_curl`](Self::to_curl) and `to_string()` flag the request as
+    /// invalid.
+    ///
     /// # Example
     ///
     /// This retrieves the 2nd to 5th entries in the result:
@@ -311,21 +463,96 @@ impl Builder {
     ///     .range(1, 4);
     /// ```
     pub fn range(mut self, low: usize, high: usize) -> Self {
-        self.headers
-            .insert("Range-Unit", HeaderValue::from_static("items"));
-        self.headers.insert(
-            "Range",
-            HeaderValue::from_str(&format!("{}-{}", low, high)).unwrap(),
-        );
+        if high < low {
+            self.invalid_range = Some((low, high));
+            self.limit = None;
+            self.offset = None;
+            self.paginate();
+            // Never render an unbounded request: PostgREST rejects this one
+            // with a 416.
+            self.headers
+                .insert("Range-Unit", HeaderValue::from_static("items"));
+            self.headers.insert(
+                "Range",
+                HeaderValue::from_str(&format!("{}-{}", low, high)).unwrap(),
+            );
+            return self;
+        }
+        self.invalid_range = None;
+        self.offset = Some(low);
+        // `None` if the range covers every row up to `usize::MAX`.
+        self.limit = (high - low).checked_add(1);
+        self.paginate();
+        self
+    }
+
+    /// Limits the result to rows from `low` onwards, i.e. the open-ended
+    /// range `low-`.
+    ///
+    /// # Example
+    ///
+    /// This retrieves every entry from the 11th onwards:
+    /// ```
+    /// use postgrest::Postgrest;
+    ///
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let parts = client
+    ///     .from("users")
+    ///     .select("*")
+    ///     .range_from(10)
+    ///     .to_request_parts();
+    /// assert_eq!(parts.headers["Range"], "10-");
+    /// ```
+    pub fn range_from(mut self, low: usize) -> Self {
+        self.invalid_range = None;
+        self.offset = Some(low);
+        self.limit = None;
+        self.paginate();
         self
     }
 
+    /// Writes the limit and offset as a `Range` header or as query
+    /// parameters, depending on the pagination mode.
+    fn paginate(&mut self) {
+        self.queries.retain(|(k, _)| k != "limit" && k != "offset");
+        if self.limit.is_none() && self.offset.is_none() {
+            return;
+        }
+        let low = self.offset.unwrap_or(0);
+        // A `Range` header can't ask for zero rows, but `limit=0` can.
+        let range = match self.limit {
+            Some(0) => None,
+            Some(count) => Some(format!("{}-{}", low, low.saturating_add(count - 1))),
+            None => Some(format!("{}-", low)),
+        };
+        match (self.pagination, range) {
+            (Pagination::Range, Some(range)) => {
+                self.headers
+                    .insert("Range-Unit", HeaderValue::from_static("items"));
+                self.headers
+                    .insert("Range", HeaderValue::from_str(&range).unwrap());
+            }
+            _ => {
+                self.headers.remove("Range-Unit");
+                self.headers.remove("Range");
+                if let Some(limit) = self.limit {
+                    self.queries.push(("limit".to_string(), limit.to_string()));
+                }
+                if let Some(offset) = self.offset {
+                    self.queries
+                        .push(("offset".to_string(), offset.to_string()));
+                }
+            }
+        }
+    }
+
     fn count(mut self, method: &str) -> Self {
-        self.headers
-            .insert("Range-Unit", HeaderValue::from_static("items"));
-        // Value is irrelevant, we just want the size
-        self.headers
-            .insert("Range", HeaderValue::from_static("0-0"));
+        // The size comes back in `Content-Range`, so without a limit a
+        // single row is enough.
+        if self.limit.is_none() {
+            self.limit = Some(1);
+            self.paginate();
+        }
         self.headers.insert(
             "Prefer",
             HeaderValue::from_str(&format!("count={}", method)).unwrap(),
@@ -333,7 +560,8 @@ impl Builder {
         self
     }
 
-    /// Retrieves the (accurate) total size of the result.
+    /// Retrieves the (accurate) total size of the result. Unless a
+    /// [`limit`](Self::limit) is set, only the first row is returned.
     ///
     /// # Example
     ///
@@ -388,6 +616,13 @@ impl Builder {
 
     /// Retrieves only one row from the result.
     ///
+    /// # Note
+    ///
+    /// PostgREST answers with a 406 if zero or several rows match. To tell
+    /// those apart, or to accept zero rows, use
+    /// [`execute_single`](Self::execute_single) or
+    /// [`maybe_single`](Self::maybe_single) instead.
+    ///
     /// # Example
     ///
     /// ```
@@ -400,13 +635,142 @@ impl Builder {
     ///     .single();
     /// ```
     pub fn single(mut self) -> Self {
-        self.headers.insert(
-            "Accept",
-            HeaderValue::from_static("application/vnd.pgrst.object+json"),
-        );
+        self.single = true;
+        self.set_accept();
+        self
+    }
+
+    /// Leaves `null` fields out of the returned rows, which shrinks
+    /// responses with sparse rows. Works with and without
+    /// [`single`](Self::single).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::Postgrest;
+    ///
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let parts = client
+    ///     .from("users")
+    ///     .select("*")
+    ///     .single()
+    ///     .strip_nulls()
+    ///     .to_request_parts();
+    /// assert_eq!(
+    ///     parts.headers["Accept"],
+    ///     "application/vnd.pgrst.object+json;nulls=stripped"
+    /// );
+    /// ```
+    pub fn strip_nulls(mut self) -> Self {
+        self.strip_nulls = true;
+        self.set_accept();
+        self
+    }
+
+    /// Asks for the result as GeoJSON, for tables and views with a PostGIS
+    /// geometry column. PostgREST answers with a `FeatureCollection` whose
+    /// features carry the other columns as properties.
+    ///
+    /// With the `geo` feature, [`execute_geojson`](Self::execute_geojson)
+    /// parses it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::Postgrest;
+    ///
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let parts = client
+    ///     .from("shops")
+    ///     .select("id,location")
+    ///     .geojson()
+    ///     .to_request_parts();
+    /// assert_eq!(parts.headers["Accept"], "application/geo+json");
+    /// ```
+    pub fn geojson(mut self) -> Self {
+        self.geojson = true;
+        self.set_accept();
+        self
+    }
+
+    /// Asks for the query plan of this request, in `format`, instead of its
+    /// result.
+    pub(crate) fn plan(mut self, format: &str, options: &ExplainOptions) -> Self {
+        let accept = self
+            .headers
+            .get("Accept")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("application/json");
+        let media_type = options.media_type(format, accept);
+        self.headers
+            .insert("Accept", HeaderValue::from_str(&media_type).unwrap());
+        self
+    }
+
+    /// Asks for the response in `media_type`, e.g. `text/csv`,
+    /// `application/octet-stream` for `bytea` results, or a type served by
+    /// a custom media type handler. This takes precedence over
+    /// [`single`](Self::single), [`strip_nulls`](Self::strip_nulls) and
+    /// [`geojson`](Self::geojson).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::Postgrest;
+    ///
+    /// # async fn run() -> Result<(), postgrest::Error> {
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let png = client
+    ///     .rpc("avatar", r#"{"username": "soedirgo"}"#)
+    ///     .accept("image/png")
+    ///     .execute_bytes()
+    ///     .await?;
+    /// std::fs::write("avatar.png", png).unwrap();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn accept<T>(mut self, media_type: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.media_type = Some(media_type.into());
+        self.set_accept();
         self
     }
 
+    /// Asks for a JSON array again, undoing [`single`](Self::single),
+    /// [`geojson`](Self::geojson) and [`accept`](Self::accept).
+    pub(crate) fn array(mut self) -> Self {
+        self.single = false;
+        self.geojson = false;
+        self.media_type = None;
+        self.set_accept();
+        self
+    }
+
+    /// Asks for at least two rows, enough to tell one row from several,
+    /// keeping a larger limit.
+    pub(crate) fn at_most_two(self) -> Self {
+        match self.limit {
+            Some(limit) if limit >= 2 => self,
+            _ => self.limit(2),
+        }
+    }
+
+    fn set_accept(&mut self) {
+        let accept = match &self.media_type {
+            Some(media_type) => HeaderValue::from_str(media_type).unwrap(),
+            None => HeaderValue::from_static(match (self.single, self.strip_nulls) {
+                _ if self.geojson => "application/geo+json",
+                (false, false) => "application/json",
+                (false, true) => "application/vnd.pgrst.array+json;nulls=stripped",
+                (true, false) => "application/vnd.pgrst.object+json",
+                (true, true) => "application/vnd.pgrst.object+json;nulls=stripped",
+            }),
+        };
+        self.headers.insert("Accept", accept);
+    }
+
     /// Performs an INSERT of the `body` (in JSON) into the table.
     ///
     /// # Example
@@ -538,6 +902,47 @@ impl Builder {
         self
     }
 
+    /// Performs a HEAD on the table: PostgREST answers with the headers a GET
+    /// would get, e.g. `Content-Range`, but without a body. Combine it with
+    /// the count methods to count rows without fetching them.
+    ///
+    /// A HEAD request has no body, so the body and the `Prefer: return=...`
+    /// of an earlier [`insert`](Self::insert), [`update`](Self::update) or
+    /// [`rpc`](Self::rpc) are dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::Postgrest;
+    ///
+    /// # async fn run() -> Result<(), postgrest::Error> {
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let resp = client
+    ///     .from("users")
+    ///     .eq("status", "ONLINE")
+    ///     .exact_count()
+    ///     .head()
+    ///     .execute()
+    ///     .await?;
+    /// // e.g. `0-0/42`
+    /// println!("{:?}", resp.headers().get("Content-Range"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn head(mut self) -> Self {
+        self.method = Method::HEAD;
+        self.body = None;
+        self.headers.remove("Content-Type");
+        let returns = self
+            .headers
+            .get("Prefer")
+            .is_some_and(|prefer| !prefer.as_bytes().starts_with(b"count="));
+        if returns {
+            self.headers.remove("Prefer");
+        }
+        self
+    }
+
     /// Performs a stored procedure call. This should only be used through the
     /// `rpc()` method in `Postgrest`.
     pub fn rpc<T>(mut self, params: T) -> Self
@@ -550,57 +955,715 @@ impl Builder {
         self
     }
 
-    /// Build the PostgREST request.
-    pub fn build(mut self) -> reqwest::RequestBuilder {
-        if let Some(schema) = self.schema {
-            let key = match self.method {
-                Method::GET | Method::HEAD => "Accept-Profile",
-                _ => "Content-Profile",
-            };
-            self.headers
-                .insert(key, HeaderValue::from_str(&schema).unwrap());
-        }
-        match self.method {
-            Method::GET | Method::HEAD => {}
-            _ => {
-                self.headers
-                    .insert("Content-Type", HeaderValue::from_static("application/json"));
-            }
-        };
-        self.client
-            .request(self.method, self.url)
-            .headers(self.headers)
-            .query(&self.queries)
-            .body(self.body.unwrap_or_default())
+    /// Retries transient failures of this request according to `policy`,
+    /// overriding the client's policy.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::{Postgrest, RetryPolicy};
+    ///
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// client
+    ///     .from("users")
+    ///     .select("*")
+    ///     .retry(RetryPolicy::new().max_attempts(5));
+    /// ```
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
     }
 
-    /// Executes the PostgREST request.
-    pub async fn execute(self) -> Result<Response, Error> {
-        self.build().send().await
+    /// Drops the retry policy inherited from the client.
+    pub(crate) fn without_retry(mut self) -> Self {
+        self.retry = None;
+        self
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    const TABLE_URL: &str = "http://localhost:3000/table";
-    const RPC_URL: &str = "http://localhost:3000/rpc";
 
-    #[test]
-    fn only_accept_json() {
-        let client = Client::new();
-        let builder = Builder::new(TABLE_URL, None, HeaderMap::new(), client);
-        assert_eq!(
-            builder.headers.get("Accept").unwrap(),
-            HeaderValue::from_static("application/json")
-        );
+    /// Marks the request as safe to repeat, so that it is retried even though
+    /// it isn't a `GET` or `HEAD`. Useful for RPCs that don't modify data.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::{Postgrest, RetryPolicy};
+    ///
+    /// let client = Postgrest::new("https://your.postgrest.endpoint")
+    ///     .retry(RetryPolicy::new());
+    /// client
+    ///     .rpc("add", r#"{"a": 1, "b": 2}"#)
+    ///     .idempotent();
+    /// ```
+    pub fn idempotent(mut self) -> Self {
+        self.idempotent = true;
+        self
     }
 
-    #[test]
-    fn auth_with_token() {
-        let client = Client::new();
-        let builder = Builder::new(TABLE_URL, None, HeaderMap::new(), client).auth("$Up3rS3crET");
+    /// Registers `middleware` to run around this request, after the
+    /// client's middleware.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::{Middleware, Postgrest, RequestParts};
+    ///
+    /// struct Logger;
+    ///
+    /// impl Middleware for Logger {
+    ///     fn on_request(&self, request: &mut RequestParts) {
+    ///         println!("{} {}", request.method, request.url);
+    ///     }
+    /// }
+    ///
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// client
+    ///     .from("users")
+    ///     .select("*")
+    ///     .middleware(Logger);
+    /// ```
+    pub fn middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: Middleware + 'static,
+    {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Replaces filter values with `?` in the query string recorded on the
+    /// request's tracing span, keeping only columns and operators.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::Postgrest;
+    ///
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// client
+    ///     .from("users")
+    ///     .eq("email", "someone@example.com")
+    ///     .redact_query_values();
+    /// ```
+    #[cfg(feature = "tracing")]
+    pub fn redact_query_values(mut self) -> Self {
+        self.redact_query_values = true;
+        self
+    }
+
+    #[cfg(feature = "tracing")]
+    fn span(&self) -> tracing::Span {
+        use tracing::field::Empty;
+
+        let name = self.url.rsplit('/').next().unwrap_or_default();
+        let name = percent_encoding::percent_decode_str(name).decode_utf8_lossy();
+        let (table, function) = if self.is_rpc {
+            (None, Some(&*name))
+        } else {
+            (Some(&*name), None)
+        };
+        tracing::info_span!(
+            "postgrest",
+            table,
+            function,
+            method = %self.method,
+            schema = self.schema.as_deref(),
+            query = %crate::trace::query_string(&self.queries, self.redact_query_values),
+            status = Empty,
+            error_code = Empty,
+            content_range = Empty,
+            total = Empty,
+            error = Empty,
+        )
+    }
+
+    fn request_parts(&self) -> RequestParts {
+        let mut headers = self.headers.clone();
+        if let Some(schema) = &self.schema {
+            let key = match self.method {
+                Method::GET | Method::HEAD => "Accept-Profile",
+                _ => "Content-Profile",
+            };
+            headers.insert(key, HeaderValue::from_str(schema).unwrap());
+        }
+        match self.method {
+            Method::GET | Method::HEAD => {}
+            _ => {
+                headers.insert("Content-Type", HeaderValue::from_static("application/json"));
+            }
+        };
+        RequestParts::new(
+            self.method.clone(),
+            &self.url,
+            &self.queries,
+            headers,
+            self.body.clone(),
+        )
+    }
+
+    /// Renders the request that `execute()` would send, without sending it.
+    ///
+    /// # Note
+    ///
+    /// Middleware isn't applied, since it may have side effects.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::Postgrest;
+    ///
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let parts = client
+    ///     .from("users")
+    ///     .select("username")
+    ///     .eq("status", "ONLINE")
+    ///     .to_request_parts();
+    /// assert_eq!(parts.method, "GET");
+    /// assert_eq!(
+    ///     parts.url,
+    ///     "https://your.postgrest.endpoint/users?select=username&status=eq.ONLINE"
+    /// );
+    /// ```
+    pub fn to_request_parts(&self) -> RequestParts {
+        self.request_parts()
+    }
+
+    /// Renders a ready-to-run curl command sending the request that
+    /// `execute()` would send.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::Postgrest;
+    ///
+    /// let client = Postgrest::new("https://your.postgrest.endpoint").schema("personal");
+    /// let curl = client
+    ///     .from("users")
+    ///     .select("username")
+    ///     .to_curl();
+    /// assert_eq!(
+    ///     curl,
+    ///     "curl 'https://your.postgrest.endpoint/users?select=username' \\\n  \
+    ///      -H 'accept: application/json' \\\n  \
+    ///      -H 'accept-profile: personal'"
+    /// );
+    /// ```
+    pub fn to_curl(&self) -> String {
+        self.flag_invalid(self.to_request_parts().to_curl(false))
+    }
+
+    /// Like [`to_curl`](Self::to_curl), but masks the credentials in the
+    /// `Authorization` and `apikey` headers so the command can be logged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::Postgrest;
+    ///
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let curl = client
+    ///     .from("users")
+    ///     .auth("supers.ecretjw.ttoken")
+    ///     .to_curl_redacted();
+    /// assert!(curl.contains("-H 'authorization: Bearer [REDACTED]'"));
+    /// ```
+    pub fn to_curl_redacted(&self) -> String {
+        self.flag_invalid(self.to_request_parts().to_curl(true))
+    }
+
+    /// Fails with the error `execute()` would return before sending.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        match self.invalid_range {
+            Some((low, high)) => Err(Error::InvalidRange { low, high }),
+            None => Ok(()),
+        }
+    }
+
+    /// Prefixes a rendered request with a comment line if `execute()`
+    /// would refuse to send it.
+    fn flag_invalid(&self, rendered: String) -> String {
+        match self.validate() {
+            Ok(()) => rendered,
+            Err(err) => format!("# {}\n{}", err, rendered),
+        }
+    }
+
+    pub(crate) fn header(mut self, name: &'static str, value: &'static str) -> Self {
+        self.headers.insert(name, HeaderValue::from_static(value));
+        self
+    }
+
+    pub(crate) fn client(&self) -> &C {
+        &self.client
+    }
+
+    // Shared by the async and blocking `execute()`.
+    pub(crate) fn into_call(self) -> Result<Call<C>, Error> {
+        self.validate()?;
+        let retry = self
+            .retry
+            .clone()
+            .filter(|policy| policy.allows(&self.method, self.idempotent));
+        let mut parts = self.request_parts();
+        self.middlewares.on_request(&mut parts);
+        Ok(Call {
+            parts,
+            retry,
+            #[cfg(feature = "tracing")]
+            span: self.span(),
+            middlewares: self.middlewares,
+            client: self.client,
+        })
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl Builder {
+    /// Build the PostgREST request.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the request is invalid, e.g. after a [`range`](Self::range)
+    /// whose upper bound is below its lower bound. Use
+    /// [`try_build`](Self::try_build) to get the error instead.
+    pub fn build(self) -> reqwest::RequestBuilder {
+        match self.try_build() {
+            Ok(request) => request,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Build the PostgREST request, failing like `execute()` would for an
+    /// invalid request.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::{Error, Postgrest};
+    ///
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let request = client.from("users").range(20, 10).try_build();
+    /// assert!(matches!(request, Err(Error::InvalidRange { low: 20, high: 10 })));
+    /// ```
+    pub fn try_build(self) -> Result<reqwest::RequestBuilder, Error> {
+        self.validate()?;
+        Ok(self.request_parts().into_request(&self.client))
+    }
+}
+
+impl<T: Transport> Builder<T> {
+    /// Executes the PostgREST request.
+    pub async fn execute(self) -> Result<Response, Error> {
+        let call = self.into_call()?;
+        let result = send(&call.client, call.parts.clone(), call.retry.clone());
+        #[cfg(feature = "tracing")]
+        let result = tracing::Instrument::instrument(result, call.span.clone());
+        let result = result.await;
+        match &result {
+            Ok(resp) => call.on_response(resp.status(), resp.headers()),
+            Err(err) => call.on_error(err),
+        }
+        result
+    }
+
+    /// Checks whether any row matches the filters, with a HEAD request for
+    /// at most one row.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::Postgrest;
+    ///
+    /// # async fn run() -> Result<(), postgrest::Error> {
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let taken = client
+    ///     .from("users")
+    ///     .eq("username", "soedirgo")
+    ///     .exists()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn exists(self) -> Result<bool, Error> {
+        let resp = self.array().head().limit(1).execute().await?;
+        rows_exist(resp.status(), resp.headers())
+    }
+
+    /// Executes the request and deserializes its only row, or returns
+    /// `None` if no row matched.
+    ///
+    /// Unlike [`single`](Self::single), zero rows isn't an error. More than
+    /// one row is, [`Error::MultipleRows`]. At most two rows are requested
+    /// unless a larger [`limit`](Self::limit) is set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::Postgrest;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct User {
+    ///     username: String,
+    /// }
+    ///
+    /// # async fn run() -> Result<(), postgrest::Error> {
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let user: Option<User> = client
+    ///     .from("users")
+    ///     .eq("username", "soedirgo")
+    ///     .maybe_single()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn maybe_single<R>(self) -> Result<Option<R>, Error>
+    where
+        R: DeserializeOwned,
+    {
+        let mut rows = self.at_most_two().execute_stream::<R>().await?;
+        let row = rows.try_next().await?;
+        if row.is_some() && rows.try_next().await?.is_some() {
+            return Err(Error::MultipleRows);
+        }
+        Ok(row)
+    }
+
+    /// Executes the request and deserializes its only row. No rows is
+    /// [`Error::NoRows`], more than one [`Error::MultipleRows`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::{Error, Postgrest};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct User {
+    ///     username: String,
+    /// }
+    ///
+    /// # async fn run() -> Result<(), postgrest::Error> {
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// match client.from("users").eq("username", "soedirgo").execute_single::<User>().await {
+    ///     Ok(user) => println!("found {}", user.username),
+    ///     Err(Error::NoRows) => println!("no such user"),
+    ///     Err(err) => return Err(err),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_single<R>(self) -> Result<R, Error>
+    where
+        R: DeserializeOwned,
+    {
+        self.maybe_single().await?.ok_or(Error::NoRows)
+    }
+
+    /// Returns the query plan of this request instead of running it, e.g.
+    /// to catch sequential scans in tests. PostgREST only serves plans when
+    /// `db-plan-enabled` is set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::{ExplainOptions, Postgrest};
+    ///
+    /// # async fn run() -> Result<(), postgrest::Error> {
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let explain = client
+    ///     .from("users")
+    ///     .eq("username", "soedirgo")
+    ///     .explain(ExplainOptions::new().analyze())
+    ///     .await?;
+    /// assert!(explain.plan.nodes().all(|node| node.node_type != "Seq Scan"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn explain(self, options: ExplainOptions) -> Result<Explain, Error> {
+        let body = self.plan("json", &options).execute_text().await?;
+        Ok(Explain::from_json(&body)?)
+    }
+
+    /// Returns the query plan of this request in PostgreSQL's text format.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::{ExplainOptions, Postgrest};
+    ///
+    /// # async fn run() -> Result<(), postgrest::Error> {
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let plan = client
+    ///     .from("users")
+    ///     .select("*")
+    ///     .explain_text(ExplainOptions::new())
+    ///     .await?;
+    /// println!("{}", plan);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn explain_text(self, options: ExplainOptions) -> Result<String, Error> {
+        self.plan("text", &options).execute_text().await
+    }
+
+    /// Executes the request as GeoJSON and parses the features.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::Postgrest;
+    ///
+    /// # async fn run() -> Result<(), postgrest::Error> {
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let shops = client
+    ///     .from("shops")
+    ///     .select("id,location")
+    ///     .execute_geojson()
+    ///     .await?;
+    /// for shop in shops.features {
+    ///     println!("{:?}: {:?}", shop.property("id"), shop.geometry);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "geo")]
+    pub async fn execute_geojson(self) -> Result<geojson::FeatureCollection, Error> {
+        let body = self.geojson().execute_text().await?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Executes the request and returns the raw body, e.g. after
+    /// [`accept`](Self::accept). Error statuses become [`Error::Status`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::Postgrest;
+    ///
+    /// # async fn run() -> Result<(), postgrest::Error> {
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let csv = client
+    ///     .from("users")
+    ///     .select("*")
+    ///     .accept("text/csv")
+    ///     .execute_bytes()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_bytes(self) -> Result<Bytes, Error> {
+        self.execute_ok().await?.bytes().await
+    }
+
+    /// Executes the request and streams the raw body as it arrives, so large
+    /// downloads don't have to fit in memory.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures_util::TryStreamExt;
+    /// use postgrest::Postgrest;
+    ///
+    /// # async fn run() -> Result<(), postgrest::Error> {
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let mut chunks = client
+    ///     .rpc("export", "{}")
+    ///     .accept("application/octet-stream")
+    ///     .execute_bytes_stream()
+    ///     .await?;
+    /// while let Some(chunk) = chunks.try_next().await? {
+    ///     println!("{} bytes", chunk.len());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_bytes_stream(
+        self,
+    ) -> Result<impl Stream<Item = Result<Bytes, Error>> + Unpin, Error> {
+        let resp = self.execute_ok().await?;
+        Ok(resp.bytes_stream())
+    }
+
+    /// Executes the request and yields the rows of the result one by one as
+    /// they arrive, without buffering the whole JSON array. Memory use stays
+    /// bounded by the largest row, e.g. for exports.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures_util::TryStreamExt;
+    /// use postgrest::Postgrest;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Message {
+    ///     id: i64,
+    ///     message: String,
+    /// }
+    ///
+    /// # async fn run() -> Result<(), postgrest::Error> {
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let mut messages = client
+    ///     .from("messages")
+    ///     .select("id,message")
+    ///     .execute_stream::<Message>()
+    ///     .await?;
+    /// while let Some(message) = messages.try_next().await? {
+    ///     println!("{}: {}", message.id, message.message);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_stream<R>(
+        self,
+    ) -> Result<impl Stream<Item = Result<R, Error>> + Unpin, Error>
+    where
+        R: DeserializeOwned,
+    {
+        let body = self.array().execute_bytes_stream().await?;
+        Ok(stream::decode(body, JsonArrayDecoder::new()))
+    }
+
+    /// Executes the request as CSV and yields its records one by one as they
+    /// arrive. The first record is the header with the column names.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use futures_util::TryStreamExt;
+    /// use postgrest::Postgrest;
+    ///
+    /// # async fn run() -> Result<(), postgrest::Error> {
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let mut records = client
+    ///     .from("messages")
+    ///     .select("id,message")
+    ///     .execute_csv_stream()
+    ///     .await?;
+    /// let header = records.try_next().await?;
+    /// while let Some(record) = records.try_next().await? {
+    ///     println!("{}", record.join("\t"));
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_csv_stream(
+        self,
+    ) -> Result<impl Stream<Item = Result<Vec<String>, Error>> + Unpin, Error> {
+        let body = self.accept("text/csv").execute_bytes_stream().await?;
+        Ok(stream::decode(body, CsvDecoder::default()))
+    }
+
+    /// Executes the request and returns the body of a successful response.
+    async fn execute_text(self) -> Result<String, Error> {
+        self.execute_ok().await?.text().await
+    }
+
+    /// Executes the request, turning error statuses into [`Error::Status`].
+    async fn execute_ok(self) -> Result<Response, Error> {
+        let resp = self.execute().await?;
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await?;
+            return Err(Error::Status { status, body });
+        }
+        Ok(resp)
+    }
+}
+
+/// Reads the answer to an `exists()` request from its `Content-Range`
+/// header, which is `*/*` when no row matched.
+pub(crate) fn rows_exist(status: StatusCode, headers: &HeaderMap) -> Result<bool, Error> {
+    if !status.is_success() {
+        return Err(Error::Status {
+            status,
+            body: String::new(),
+        });
+    }
+    let range = headers
+        .get("Content-Range")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("*");
+    Ok(!range.starts_with('*'))
+}
+
+/// A request ready to be sent, after middleware has run.
+pub(crate) struct Call<C> {
+    pub(crate) client: C,
+    pub(crate) parts: RequestParts,
+    pub(crate) retry: Option<RetryPolicy>,
+    middlewares: Middlewares,
+    #[cfg(feature = "tracing")]
+    pub(crate) span: tracing::Span,
+}
+
+impl<C> Call<C> {
+    pub(crate) fn on_response(&self, status: StatusCode, headers: &HeaderMap) {
+        #[cfg(feature = "tracing")]
+        crate::trace::record_response(&self.span, status, headers);
+        self.middlewares.on_response(&self.parts, status, headers);
+    }
+
+    pub(crate) fn on_error(&self, error: &Error) {
+        #[cfg(feature = "tracing")]
+        self.span.record("error", tracing::field::display(error));
+        self.middlewares.on_error(&self.parts, error);
+    }
+}
+
+/// Renders the request as returned by [`Builder::to_request_parts`],
+/// after a comment line if `execute()` would refuse to send it.
+impl<C> fmt::Display for Builder<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Err(err) = self.validate() {
+            writeln!(f, "# {}", err)?;
+        }
+        self.to_request_parts().fmt(f)
+    }
+}
+
+async fn send<T: Transport>(
+    transport: &T,
+    parts: RequestParts,
+    policy: Option<RetryPolicy>,
+) -> Result<Response, Error> {
+    let policy = match policy {
+        Some(policy) => policy,
+        None => return transport.send(parts).await,
+    };
+
+    let mut attempt = 1;
+    loop {
+        let result = transport.send(parts.clone()).await;
+        let outcome = result.as_ref().map(|resp| (resp.status(), resp.headers()));
+        match policy.next_delay(attempt, outcome) {
+            Some(delay) => transport.sleep(delay).await,
+            None => return result,
+        }
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE_URL: &str = "http://localhost:3000/table";
+    const RPC_URL: &str = "http://localhost:3000/rpc";
+
+    #[test]
+    fn only_accept_json() {
+        let client = Client::new();
+        let builder = Builder::new(TABLE_URL, None, HeaderMap::new(), client);
+        assert_eq!(
+            builder.headers.get("Accept").unwrap(),
+            HeaderValue::from_static("application/json")
+        );
+    }
+
+    #[test]
+    fn auth_with_token() {
+        let client = Client::new();
+        let builder = Builder::new(TABLE_URL, None, HeaderMap::new(), client).auth("$Up3rS3crET");
         assert_eq!(
             builder.headers.get("Authorization").unwrap(),
             HeaderValue::from_static("Bearer $Up3rS3crET")
@@ -612,27 +1675,49 @@ mod tests {
         let client = Client::new();
         let builder = Builder::new(TABLE_URL, None, HeaderMap::new(), client).select("some_table");
         assert_eq!(builder.method, Method::GET);
-        assert_eq!(
-            builder
-                .queries
-                .contains(&("select".to_string(), "some_table".to_string())),
-            true
-        );
+        assert!(builder
+            .queries
+            .contains(&("select".to_string(), "some_table".to_string())));
+    }
+
+    #[test]
+    fn select_replaces_only_table_selection() {
+        let builder = Builder::new(TABLE_URL, None, HeaderMap::new(), Client::new());
+        let parts = builder
+            .clone()
+            .table_select("id,username".to_string())
+            .select("status")
+            .to_request_parts();
+        assert_eq!(parts.url, format!("{}?select=status", TABLE_URL));
+        let parts = builder.select("id").select("status").to_request_parts();
+        assert_eq!(parts.url, format!("{}?select=id&select=status", TABLE_URL));
     }
 
     #[test]
     fn order_assert_query() {
         let client = Client::new();
         let builder = Builder::new(TABLE_URL, None, HeaderMap::new(), client).order("id");
+        assert!(builder
+            .queries
+            .contains(&("order".to_string(), "id".to_string())));
+    }
+
+    #[test]
+    fn order_merges_terms() {
+        let client = Client::new();
+        let parts = Builder::new(TABLE_URL, None, HeaderMap::new(), client)
+            .order("id")
+            .order(Order::desc("name").nulls_first())
+            .order(Order::asc("name").referenced_table("cities"))
+            .to_request_parts();
         assert_eq!(
-            builder
-                .queries
-                .contains(&("order".to_string(), "id".to_string())),
-            true
+            parts.url,
+            "http://localhost:3000/table?order=id%2Cname.desc.nullsfirst&cities.order=name.asc"
         );
     }
 
     #[test]
+    #[allow(deprecated)]
     fn order_with_options_assert_query() {
         let client = Client::new();
         let builder = Builder::new(TABLE_URL, None, HeaderMap::new(), client).order_with_options(
@@ -641,14 +1726,205 @@ mod tests {
             true,
             false,
         );
+        assert!(builder
+            .queries
+            .contains(&("cities.order".to_string(), "name.asc.nullslast".to_string())));
+    }
+
+    #[derive(Clone)]
+    struct ContentRange(&'static str);
+
+    impl Transport for ContentRange {
+        fn send(&self, request: RequestParts) -> crate::SendFuture<'_> {
+            assert_eq!(request.method, Method::HEAD);
+            assert!(request.body.is_none());
+            assert!(request.headers.get("Prefer").is_none());
+            assert!(request.headers.get("Content-Type").is_none());
+            assert_eq!(request.headers.get("Range").unwrap(), "0-0");
+            assert_eq!(request.headers.get("Accept").unwrap(), "application/json");
+            let mut headers = HeaderMap::new();
+            headers.insert("Content-Range", HeaderValue::from_static(self.0));
+            Box::pin(async move { Ok(crate::transport::response(StatusCode::OK, headers, "")) })
+        }
+    }
+
+    #[tokio::test]
+    async fn exists_reads_content_range() {
+        let found = Builder::new(TABLE_URL, None, HeaderMap::new(), ContentRange("0-0/*"));
+        assert!(found.eq("id", "1").exists().await.unwrap());
+        let missing = Builder::new(TABLE_URL, None, HeaderMap::new(), ContentRange("*/*"));
+        assert!(!missing.eq("id", "2").exists().await.unwrap());
+        // An object response would be a 406 when nothing matched.
+        let single = Builder::new(TABLE_URL, None, HeaderMap::new(), ContentRange("*/*"));
+        assert!(!single.eq("id", "3").single().exists().await.unwrap());
+        let insert = Builder::new(TABLE_URL, None, HeaderMap::new(), ContentRange("0-0/*"));
+        assert!(insert.insert(r#"{"id":4}"#).exists().await.unwrap());
+    }
+
+    #[test]
+    fn head_drops_body_but_keeps_count() {
+        let client = crate::Postgrest::new("http://localhost:3000");
+        let parts = client
+            .from("users")
+            .update(r#"{"status":"OFFLINE"}"#)
+            .exact_count()
+            .head()
+            .to_request_parts();
+        assert_eq!(parts.method, Method::HEAD);
+        assert!(parts.body.is_none());
+        assert!(parts.headers.get("Content-Type").is_none());
+        assert_eq!(parts.headers.get("Prefer").unwrap(), "count=exact");
+    }
+
+    #[derive(Clone)]
+    struct Rows(&'static str, &'static str);
+
+    impl Transport for Rows {
+        fn send(&self, request: RequestParts) -> crate::SendFuture<'_> {
+            assert_eq!(request.headers.get("Range").unwrap(), self.0);
+            assert_eq!(request.headers.get("Accept").unwrap(), "application/json");
+            let body = self.1;
+            Box::pin(async move {
+                Ok(crate::transport::response(
+                    StatusCode::OK,
+                    HeaderMap::new(),
+                    body,
+                ))
+            })
+        }
+    }
+
+    /// Answers 503 once, then 200, and records the delays it waits.
+    #[derive(Clone, Default)]
+    struct Unavailable {
+        sends: Arc<std::sync::Mutex<u32>>,
+        sleeps: Arc<std::sync::Mutex<Vec<std::time::Duration>>>,
+    }
+
+    impl Transport for Unavailable {
+        fn send(&self, _request: RequestParts) -> crate::SendFuture<'_> {
+            let mut sends = self.sends.lock().unwrap();
+            *sends += 1;
+            let status = match *sends {
+                1 => StatusCode::SERVICE_UNAVAILABLE,
+                _ => StatusCode::OK,
+            };
+            Box::pin(async move { Ok(crate::transport::response(status, HeaderMap::new(), "[]")) })
+        }
+
+        fn sleep(&self, duration: std::time::Duration) -> crate::SleepFuture<'_> {
+            self.sleeps.lock().unwrap().push(duration);
+            Box::pin(async {})
+        }
+    }
+
+    #[test]
+    fn retries_wait_with_transport_sleep() {
+        use std::future::Future;
+        use std::task::{Context, Poll, Waker};
+
+        let transport = Unavailable::default();
+        let policy = RetryPolicy::new()
+            .jitter(false)
+            .initial_backoff(std::time::Duration::from_millis(100));
+        let execute = Builder::new(TABLE_URL, None, HeaderMap::new(), transport.clone())
+            .retry(policy)
+            .execute();
+        // Polled outside any runtime, so tokio's timer would panic.
+        let poll = std::pin::pin!(execute).poll(&mut Context::from_waker(Waker::noop()));
+        match poll {
+            Poll::Ready(resp) => assert_eq!(resp.unwrap().status(), StatusCode::OK),
+            Poll::Pending => panic!("retry didn't complete"),
+        }
+        assert_eq!(*transport.sends.lock().unwrap(), 2);
+        assert_eq!(
+            *transport.sleeps.lock().unwrap(),
+            [std::time::Duration::from_millis(100)]
+        );
+    }
+
+    #[tokio::test]
+    async fn maybe_single_reads_at_most_two_rows() {
+        let builder =
+            |range, body| Builder::new(TABLE_URL, None, HeaderMap::new(), Rows(range, body));
+        let none: Option<i32> = builder("0-1", "[]").maybe_single().await.unwrap();
+        assert_eq!(none, None);
+        let one: Option<i32> = builder("0-1", "[1]").single().maybe_single().await.unwrap();
+        assert_eq!(one, Some(1));
+        assert!(matches!(
+            builder("0-1", "[1,2]").maybe_single::<i32>().await,
+            Err(Error::MultipleRows)
+        ));
+        assert!(matches!(
+            builder("0-9", "[1,2,3,4,5]")
+                .limit(10)
+                .maybe_single::<i32>()
+                .await,
+            Err(Error::MultipleRows)
+        ));
+        // `exact_count` alone asks for a single row.
+        assert!(matches!(
+            builder("0-1", "[1,2]")
+                .exact_count()
+                .maybe_single::<i32>()
+                .await,
+            Err(Error::MultipleRows)
+        ));
+    }
+
+    #[test]
+    fn zero_limit_assert_query() {
+        let client = Client::new();
+        let parts = Builder::new(TABLE_URL, None, HeaderMap::new(), client)
+            .offset(10)
+            .limit(0)
+            .to_request_parts();
+        assert!(parts.headers.get("Range").is_none());
+        assert_eq!(parts.url, "http://localhost:3000/table?limit=0&offset=10");
+    }
+
+    #[test]
+    fn open_ended_ranges_assert_range_header() {
+        let client = Client::new();
+        let builder = Builder::new(TABLE_URL, None, HeaderMap::new(), client).range_from(10);
+        let range = |builder: &Builder| builder.to_request_parts().headers["Range"].clone();
+        assert_eq!(range(&builder), "10-");
+        let builder = builder.range(0, usize::MAX);
+        assert_eq!(range(&builder), "0-");
+        let builder = builder.range(usize::MAX, usize::MAX);
         assert_eq!(
-            builder
-                .queries
-                .contains(&("cities.order".to_string(), "name.asc.nullslast".to_string())),
-            true
+            range(&builder).to_str().unwrap(),
+            format!("{0}-{0}", usize::MAX)
         );
     }
 
+    #[test]
+    fn invalid_range_fails_before_sending() {
+        let client = Client::new();
+        let builder = Builder::new(TABLE_URL, None, HeaderMap::new(), client)
+            .pagination(Pagination::Query)
+            .limit(5)
+            .range(20, 10);
+        let parts = builder.to_request_parts();
+        assert_eq!(parts.headers["Range"], "20-10");
+        assert_eq!(parts.url, TABLE_URL);
+        assert!(builder
+            .to_curl()
+            .starts_with("# invalid range 20-10: upper bound is below lower bound\ncurl "));
+        assert!(builder.to_string().starts_with("# invalid range 20-10"));
+        assert!(matches!(
+            builder.clone().try_build(),
+            Err(Error::InvalidRange { low: 20, high: 10 })
+        ));
+        assert!(matches!(
+            builder.clone().into_call(),
+            Err(Error::InvalidRange { low: 20, high: 10 })
+        ));
+        let builder = builder.range(10, 20);
+        assert!(builder.to_curl().starts_with("curl "));
+        assert!(builder.into_call().is_ok());
+    }
+
     #[test]
     fn limit_assert_range_header() {
         let client = Client::new();
@@ -659,17 +1935,68 @@ mod tests {
         );
     }
 
+    #[test]
+    fn offset_and_limit_compose_range_header() {
+        let client = Client::new();
+        let builder = Builder::new(TABLE_URL, None, HeaderMap::new(), client)
+            .offset(20)
+            .limit(10);
+        let range = |builder: &Builder| builder.to_request_parts().headers["Range"].clone();
+        assert_eq!(range(&builder), "20-29");
+        let builder = builder.offset(5);
+        assert_eq!(range(&builder), "5-14");
+    }
+
+    #[test]
+    fn query_pagination_assert_query() {
+        let client = Client::new();
+        let builder = Builder::new(TABLE_URL, None, HeaderMap::new(), client)
+            .pagination(Pagination::Query)
+            .range(10, 19)
+            .limit(5);
+        let parts = builder.to_request_parts();
+        assert!(parts.headers.get("Range").is_none());
+        assert_eq!(parts.url, "http://localhost:3000/table?limit=5&offset=10");
+
+        let parts = builder.pagination(Pagination::Range).to_request_parts();
+        assert_eq!(parts.url, TABLE_URL);
+        assert_eq!(parts.headers["Range"], "10-14");
+    }
+
+    #[test]
+    fn count_keeps_limit_in_either_order() {
+        let builder = || Builder::new(TABLE_URL, None, HeaderMap::new(), Client::new());
+        for builder in [
+            builder().limit(10).exact_count(),
+            builder().exact_count().limit(10),
+        ] {
+            let parts = builder.to_request_parts();
+            assert_eq!(parts.headers["Range"], "0-9");
+            assert_eq!(parts.headers["Prefer"], "count=exact");
+        }
+        let parts = builder().exact_count().to_request_parts();
+        assert_eq!(parts.headers["Range"], "0-0");
+
+        let builder = || builder().pagination(Pagination::Query);
+        for builder in [
+            builder().limit(10).exact_count(),
+            builder().exact_count().limit(10),
+        ] {
+            let parts = builder.to_request_parts();
+            assert!(parts.headers.get("Range").is_none());
+            assert_eq!(parts.url, "http://localhost:3000/table?limit=10");
+            assert_eq!(parts.headers["Prefer"], "count=exact");
+        }
+    }
+
     #[test]
     fn foreign_table_limit_assert_query() {
         let client = Client::new();
         let builder = Builder::new(TABLE_URL, None, HeaderMap::new(), client)
             .foreign_table_limit(20, "some_table");
-        assert_eq!(
-            builder
-                .queries
-                .contains(&("some_table.limit".to_string(), "20".to_string())),
-            true
-        );
+        assert!(builder
+            .queries
+            .contains(&("some_table.limit".to_string(), "20".to_string())));
     }
 
     #[test]
@@ -682,6 +2009,63 @@ mod tests {
         );
     }
 
+    #[test]
+    fn strip_nulls_composes_with_single() {
+        let client = Client::new();
+        let builder = Builder::new(TABLE_URL, None, HeaderMap::new(), client).strip_nulls();
+        let accept = |builder: &Builder| builder.to_request_parts().headers["Accept"].clone();
+        assert_eq!(
+            accept(&builder),
+            "application/vnd.pgrst.array+json;nulls=stripped"
+        );
+        let builder = builder.single();
+        assert_eq!(
+            accept(&builder),
+            "application/vnd.pgrst.object+json;nulls=stripped"
+        );
+        let builder = builder.array();
+        assert_eq!(
+            accept(&builder),
+            "application/vnd.pgrst.array+json;nulls=stripped"
+        );
+    }
+
+    #[test]
+    fn plan_keeps_accepted_media_type() {
+        let client = Client::new();
+        let parts = Builder::new(TABLE_URL, None, HeaderMap::new(), client)
+            .single()
+            .plan("json", &ExplainOptions::new().analyze())
+            .to_request_parts();
+        assert_eq!(
+            parts.headers["Accept"],
+            "application/vnd.pgrst.plan+json; for=\"application/vnd.pgrst.object+json\"; \
+             options=analyze"
+        );
+    }
+
+    #[test]
+    fn geojson_assert_accept_header() {
+        let client = Client::new();
+        let parts = Builder::new(TABLE_URL, None, HeaderMap::new(), client)
+            .geojson()
+            .strip_nulls()
+            .to_request_parts();
+        assert_eq!(parts.headers["Accept"], "application/geo+json");
+    }
+
+    #[test]
+    fn accept_overrides_json_media_types() {
+        let client = Client::new();
+        let builder = Builder::new(TABLE_URL, None, HeaderMap::new(), client)
+            .accept("text/csv")
+            .single();
+        let accept = |builder: &Builder| builder.to_request_parts().headers["Accept"].clone();
+        assert_eq!(accept(&builder), "text/csv");
+        let builder = builder.array();
+        assert_eq!(accept(&builder), "application/json");
+    }
+
     #[test]
     fn single_assert_accept_header() {
         let client = Client::new();
@@ -706,7 +2090,7 @@ mod tests {
     fn not_rpc_should_not_have_flag() {
         let client = Client::new();
         let builder = Builder::new(TABLE_URL, None, HeaderMap::new(), client).select("ignored");
-        assert_eq!(builder.is_rpc, false);
+        assert!(!builder.is_rpc);
     }
 
     #[test]
@@ -715,7 +2099,76 @@ mod tests {
         let builder =
             Builder::new(RPC_URL, None, HeaderMap::new(), client).rpc("{\"a\": 1, \"b\": 2}");
         assert_eq!(builder.body.unwrap(), "{\"a\": 1, \"b\": 2}");
-        assert_eq!(builder.is_rpc, true);
+        assert!(builder.is_rpc);
+    }
+
+    #[tokio::test]
+    async fn idempotent_calls_are_retried() {
+        let policy = || RetryPolicy::new().jitter(false);
+        let transport = Unavailable::default();
+        let resp = Builder::new(RPC_URL, None, HeaderMap::new(), transport.clone())
+            .rpc("{}")
+            .retry(policy())
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(*transport.sends.lock().unwrap(), 1);
+
+        let transport = Unavailable::default();
+        let resp = Builder::new(RPC_URL, None, HeaderMap::new(), transport.clone())
+            .rpc("{}")
+            .retry(policy())
+            .idempotent()
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(*transport.sends.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn request_parts_use_profile_headers() {
+        let client = Client::new();
+        let read =
+            Builder::new(TABLE_URL, Some("personal".into()), HeaderMap::new(), client).select("*");
+        let write = read.clone().update("{}");
+
+        let read = read.to_request_parts();
+        assert_eq!(read.headers.get("Accept-Profile").unwrap(), "personal");
+        assert!(read.headers.get("Content-Type").is_none());
+
+        let write = write.to_request_parts();
+        assert_eq!(write.method, Method::PATCH);
+        assert_eq!(write.headers.get("Content-Profile").unwrap(), "personal");
+        assert_eq!(
+            write.headers.get("Content-Type").unwrap(),
+            "application/json"
+        );
+        assert_eq!(write.body.as_deref(), Some("{}"));
+    }
+
+    #[test]
+    fn schema_overrides_client_schema() {
+        let client = Client::new();
+        let builder = Builder::new(TABLE_URL, Some("public".into()), HeaderMap::new(), client)
+            .schema("personal")
+            .insert("{}");
+        let parts = builder.to_request_parts();
+        assert_eq!(parts.headers.get("Content-Profile").unwrap(), "personal");
+    }
+
+    #[test]
+    fn display_renders_request() {
+        let client = Client::new();
+        let builder = Builder::new(TABLE_URL, None, HeaderMap::new(), client)
+            .select("id,name")
+            .eq("name", "New Zealand");
+        assert_eq!(
+            builder.to_string(),
+            "GET http://localhost:3000/table?select=id%2Cname&name=eq.New+Zealand\n\
+             accept: application/json\n"
+        );
     }
 
     #[test]
@@ -0,0 +1,487 @@
+use futures_util::{stream, StreamExt};
+use http::Method;
+use serde::Serialize;
+
+use crate::{
+    Builder, Error, Postgrest, QualifiedName, RelationName, Response, RetryPolicy, Transport,
+};
+
+/// How [`Postgrest::bulk_insert`] splits and sends rows.
+///
+/// # Example
+///
+/// ```
+/// use postgrest::{BulkOptions, RetryPolicy};
+///
+/// let options = BulkOptions::new()
+///     .chunk_rows(500)
+///     .chunk_bytes(1024 * 1024)
+///     .concurrency(8)
+///     .upsert("id")
+///     .retry(RetryPolicy::new().max_attempts(5));
+/// ```
+#[derive(Clone, Debug)]
+pub struct BulkOptions {
+    pub(crate) chunk_rows: usize,
+    pub(crate) chunk_bytes: usize,
+    pub(crate) concurrency: usize,
+    pub(crate) on_conflict: Option<String>,
+    pub(crate) retry: Option<RetryPolicy>,
+}
+
+impl Default for BulkOptions {
+    fn default() -> Self {
+        BulkOptions {
+            chunk_rows: 1000,
+            chunk_bytes: usize::MAX,
+            concurrency: 4,
+            on_conflict: None,
+            retry: None,
+        }
+    }
+}
+
+impl BulkOptions {
+    /// Sends chunks of up to 1000 rows, 4 at a time, as plain inserts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Puts at most `rows` rows in a chunk.
+    pub fn chunk_rows(mut self, rows: usize) -> Self {
+        self.chunk_rows = rows.max(1);
+        self
+    }
+
+    /// Keeps the JSON body of a chunk under `bytes`, e.g. a proxy's request
+    /// size limit. A single row larger than that is sent on its own.
+    pub fn chunk_bytes(mut self, bytes: usize) -> Self {
+        self.chunk_bytes = bytes;
+        self
+    }
+
+    /// Sends up to `requests` chunks at the same time.
+    pub fn concurrency(mut self, requests: usize) -> Self {
+        self.concurrency = requests.max(1);
+        self
+    }
+
+    /// Upserts instead of inserting, merging rows that conflict on the
+    /// comma-separated `columns`. Pass an empty string to use the primary key.
+    pub fn upsert<T>(mut self, columns: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.on_conflict = Some(columns.into());
+        self
+    }
+
+    /// Retries chunks that fail transiently according to `policy`.
+    ///
+    /// # Note
+    ///
+    /// An insert whose response was lost may have succeeded, so retrying it
+    /// can duplicate rows. Without this, only upserts are retried, with the
+    /// client's policy.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+}
+
+/// The outcome of [`Postgrest::bulk_insert`], one entry per chunk.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct BulkReport {
+    /// The chunks, in the order of their rows.
+    pub chunks: Vec<ChunkReport>,
+}
+
+/// The outcome of one chunk of a bulk insert.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ChunkReport {
+    /// The position of the chunk, starting at 0.
+    pub index: usize,
+    /// The number of rows in the chunk.
+    pub rows: usize,
+    /// How many requests were sent for the chunk. `0` if a row couldn't be
+    /// serialized, so the chunk was never sent.
+    pub attempts: u32,
+    /// `Ok` if PostgREST stored the rows, otherwise the last error, e.g.
+    /// [`Error::Status`] with PostgREST's message, or [`Error::Encode`] if a
+    /// row couldn't be serialized.
+    pub result: Result<(), Error>,
+}
+
+impl BulkReport {
+    /// Whether every chunk was stored.
+    pub fn is_success(&self) -> bool {
+        self.chunks.iter().all(|chunk| chunk.result.is_ok())
+    }
+
+    /// The number of rows stored.
+    pub fn rows_stored(&self) -> usize {
+        self.chunks
+            .iter()
+            .filter(|chunk| chunk.result.is_ok())
+            .map(|chunk| chunk.rows)
+            .sum()
+    }
+
+    /// The chunks that failed.
+    pub fn failed(&self) -> impl Iterator<Item = &ChunkReport> {
+        self.chunks.iter().filter(|chunk| chunk.result.is_err())
+    }
+
+    /// The chunks that needed more than one attempt.
+    pub fn retried(&self) -> impl Iterator<Item = &ChunkReport> {
+        self.chunks.iter().filter(|chunk| chunk.attempts > 1)
+    }
+}
+
+impl<T: Transport + Clone> Postgrest<T> {
+    /// Inserts `rows` into `table` in chunks, sending several chunks at a
+    /// time, and reports the outcome of each chunk. A failed chunk doesn't
+    /// stop the others.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::{BulkOptions, Postgrest};
+    /// use serde_json::json;
+    ///
+    /// # async fn run() {
+    /// let client = Postgrest::new("https://your.postgrest.endpoint");
+    /// let rows = (0..100_000).map(|id| json!({ "id": id, "message": "hi" }));
+    /// let report = client
+    ///     .bulk_insert("messages", rows, BulkOptions::new().upsert("id"))
+    ///     .await;
+    /// for chunk in report.failed() {
+    ///     eprintln!("chunk {} failed: {:?}", chunk.index, chunk.result);
+    /// }
+    /// # }
+    /// ```
+    pub async fn bulk_insert<N, I>(&self, table: N, rows: I, options: BulkOptions) -> BulkReport
+    where
+        N: RelationName,
+        I: IntoIterator,
+        I::Item: Serialize,
+    {
+        let table = table.into_qualified_name();
+        let policy = retry_policy(&options, self.retry.as_ref());
+        let chunks = Chunks::new(rows.into_iter(), &options).map(|chunk| {
+            let request = chunk
+                .body
+                .map(|body| chunk_request(self, table.clone(), body, &options));
+            send_chunk(chunk.index, chunk.rows, request, policy.as_ref())
+        });
+        let mut chunks: Vec<ChunkReport> = stream::iter(chunks)
+            .buffer_unordered(options.concurrency)
+            .collect()
+            .await;
+        chunks.sort_by_key(|chunk| chunk.index);
+        BulkReport { chunks }
+    }
+}
+
+async fn send_chunk<T: Transport + Clone>(
+    index: usize,
+    rows: usize,
+    request: Result<Builder<T>, serde_json::Error>,
+    policy: Option<&RetryPolicy>,
+) -> ChunkReport {
+    let mut report = ChunkReport {
+        index,
+        rows,
+        attempts: 0,
+        result: Ok(()),
+    };
+    let request = match request {
+        Ok(request) => request,
+        Err(err) => {
+            report.result = Err(Error::Encode(err));
+            return report;
+        }
+    };
+    loop {
+        report.attempts += 1;
+        let result = request.clone().execute().await;
+        let outcome = result.as_ref().map(|resp| (resp.status(), resp.headers()));
+        match policy.and_then(|policy| policy.next_delay(report.attempts, outcome)) {
+            Some(delay) => request.client().sleep(delay).await,
+            None => {
+                report.result = match result {
+                    Ok(resp) => stored(resp).await,
+                    Err(err) => Err(err),
+                };
+                return report;
+            }
+        }
+    }
+}
+
+async fn stored(resp: Response) -> Result<(), Error> {
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = resp.text().await?;
+    Err(Error::Status { status, body })
+}
+
+/// The policy chunks are retried with: the bulk insert's own, or the
+/// client's for upserts, which are safe to repeat.
+pub(crate) fn retry_policy(
+    options: &BulkOptions,
+    client: Option<&RetryPolicy>,
+) -> Option<RetryPolicy> {
+    options.retry.clone().or_else(|| {
+        client
+            .filter(|policy| policy.allows(&Method::POST, options.on_conflict.is_some()))
+            .cloned()
+    })
+}
+
+/// The request storing one chunk, without the client's own retries.
+pub(crate) fn chunk_request<C: Clone>(
+    client: &Postgrest<C>,
+    table: QualifiedName,
+    body: String,
+    options: &BulkOptions,
+) -> Builder<C> {
+    let builder = client.from(table).without_retry();
+    match &options.on_conflict {
+        Some(columns) if columns.is_empty() => builder
+            .upsert(body)
+            .header("Prefer", "return=minimal,resolution=merge-duplicates"),
+        Some(columns) => builder
+            .upsert(body)
+            .on_conflict(columns.as_str())
+            .header("Prefer", "return=minimal,resolution=merge-duplicates"),
+        None => builder.insert(body).header("Prefer", "return=minimal"),
+    }
+}
+
+/// A chunk of rows, serialized as a JSON array.
+pub(crate) struct Chunk {
+    pub(crate) index: usize,
+    pub(crate) rows: usize,
+    /// The first serialization error of a row, if any.
+    pub(crate) body: Result<String, serde_json::Error>,
+}
+
+/// Serializes rows lazily and groups them into chunks.
+pub(crate) struct Chunks<I> {
+    rows: I,
+    max_rows: usize,
+    max_bytes: usize,
+    index: usize,
+    pending: Option<Result<String, serde_json::Error>>,
+}
+
+impl<I> Chunks<I> {
+    pub(crate) fn new(rows: I, options: &BulkOptions) -> Self {
+        Chunks {
+            rows,
+            max_rows: options.chunk_rows,
+            max_bytes: options.chunk_bytes,
+            index: 0,
+            pending: None,
+        }
+    }
+}
+
+impl<I> Iterator for Chunks<I>
+where
+    I: Iterator,
+    I::Item: Serialize,
+{
+    type Item = Chunk;
+
+    fn next(&mut self) -> Option<Chunk> {
+        let mut body = String::from("[");
+        let mut error = None;
+        let mut rows = 0;
+        loop {
+            let row = match self.pending.take() {
+                Some(row) => row,
+                None => match self.rows.next() {
+                    Some(row) => serde_json::to_string(&row),
+                    None => break,
+                },
+            };
+            // The closing bracket, and a comma before all but the first row.
+            let too_big = matches!(&row, Ok(json) if body.len() + json.len() + 2 > self.max_bytes);
+            if rows > 0 && (rows >= self.max_rows || too_big) {
+                self.pending = Some(row);
+                break;
+            }
+            match row {
+                Ok(json) => {
+                    if rows > 0 {
+                        body.push(',');
+                    }
+                    body.push_str(&json);
+                }
+                Err(err) => {
+                    error.get_or_insert(err);
+                }
+            }
+            rows += 1;
+        }
+        if rows == 0 {
+            return None;
+        }
+        body.push(']');
+        let index = self.index;
+        self.index += 1;
+        Some(Chunk {
+            index,
+            rows,
+            body: match error {
+                Some(err) => Err(err),
+                None => Ok(body),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use http::{HeaderMap, StatusCode};
+
+    use super::*;
+    use crate::{transport, RequestParts, SendFuture};
+
+    fn bodies(rows: Vec<serde_json::Value>, options: &BulkOptions) -> Vec<String> {
+        Chunks::new(rows.into_iter(), options)
+            .map(|chunk| chunk.body.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn chunks_by_rows_and_bytes() {
+        let rows: Vec<_> = (0..5).map(serde_json::Value::from).collect();
+        let options = BulkOptions::new().chunk_rows(2);
+        assert_eq!(bodies(rows.clone(), &options), ["[0,1]", "[2,3]", "[4]"]);
+        let options = BulkOptions::new().chunk_bytes(6);
+        assert_eq!(bodies(rows, &options), ["[0,1]", "[2,3]", "[4]"]);
+        let rows = vec!["a long row".into(), "b".into()];
+        let options = BulkOptions::new().chunk_bytes(4);
+        assert_eq!(bodies(rows, &options), ["[\"a long row\"]", "[\"b\"]"]);
+    }
+
+    #[test]
+    fn unserializable_row_fails_its_chunk() {
+        let mut rows = vec![Picky(vec![1]), Picky(vec![])];
+        rows.push(Picky(vec![2]));
+        let options = BulkOptions::new().chunk_rows(2);
+        let chunks: Vec<Chunk> = Chunks::new(rows.into_iter(), &options).collect();
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].body.is_err());
+        assert_eq!(chunks[0].rows, 2);
+        assert_eq!(chunks[1].body.as_deref().unwrap(), "[[2]]");
+    }
+
+    /// Fails to serialize when empty.
+    struct Picky(Vec<i32>);
+
+    impl Serialize for Picky {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            if self.0.is_empty() {
+                return Err(serde::ser::Error::custom("empty"));
+            }
+            self.0.serialize(serializer)
+        }
+    }
+
+    /// Answers each chunk with the next status in line for its first row.
+    #[derive(Clone, Default)]
+    struct Flaky {
+        requests: Arc<Mutex<Vec<RequestParts>>>,
+    }
+
+    impl Transport for Flaky {
+        fn send(&self, request: RequestParts) -> SendFuture<'_> {
+            let mut requests = self.requests.lock().unwrap();
+            let body = request.body.clone().unwrap_or_default();
+            let seen = requests
+                .iter()
+                .filter(|r| r.body.as_deref() == Some(&body))
+                .count();
+            requests.push(request);
+            let status = match (body.as_str(), seen) {
+                ("[2,3]", 0) => StatusCode::SERVICE_UNAVAILABLE,
+                ("[4]", _) => StatusCode::CONFLICT,
+                _ => StatusCode::CREATED,
+            };
+            Box::pin(async move { Ok(transport::response(status, HeaderMap::new(), "")) })
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_each_chunk() {
+        let flaky = Flaky::default();
+        let client = Postgrest::with_transport("http://localhost:3000", flaky.clone());
+        let options = BulkOptions::new()
+            .chunk_rows(2)
+            .concurrency(2)
+            .upsert("")
+            .retry(RetryPolicy::new().initial_backoff(Duration::from_millis(1)));
+        let report = client.bulk_insert("numbers", 0..5, options).await;
+
+        let attempts: Vec<u32> = report.chunks.iter().map(|c| c.attempts).collect();
+        assert_eq!(attempts, [1, 2, 1]);
+        assert_eq!(report.rows_stored(), 4);
+        assert_eq!(report.retried().count(), 1);
+        let failed: Vec<_> = report.failed().collect();
+        assert_eq!(failed.len(), 1);
+        assert!(matches!(
+            failed[0].result,
+            Err(Error::Status {
+                status: StatusCode::CONFLICT,
+                ..
+            })
+        ));
+
+        let requests = flaky.requests.lock().unwrap();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(
+            requests[0].headers["Prefer"],
+            "return=minimal,resolution=merge-duplicates"
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_unserializable_rows() {
+        let flaky = Flaky::default();
+        let client = Postgrest::with_transport("http://localhost:3000", flaky.clone());
+        let rows = vec![Picky(vec![1]), Picky(vec![])];
+        let report = client
+            .bulk_insert("numbers", rows, BulkOptions::new())
+            .await;
+        assert_eq!(report.chunks[0].attempts, 0);
+        assert!(matches!(report.chunks[0].result, Err(Error::Encode(_))));
+        assert!(flaky.requests.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn bulk_insert_can_be_spawned() {
+        fn assert_send<F: Send>(_: F) {}
+        let client = Postgrest::new("http://localhost:3000");
+        assert_send(client.bulk_insert("numbers", vec![1, 2], BulkOptions::new()));
+    }
+
+    #[test]
+    fn retries_only_upserts_with_client_policy() {
+        let client = RetryPolicy::new();
+        assert!(retry_policy(&BulkOptions::new(), Some(&client)).is_none());
+        assert!(retry_policy(&BulkOptions::new().upsert("id"), Some(&client)).is_some());
+        let own = BulkOptions::new().retry(RetryPolicy::new());
+        assert!(retry_policy(&own, None).is_some());
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::Order;
+
+/// A typed reference to a column of the table modelled by `T`, holding
+/// values of type `V`.
+///
+/// Filters accept a `Column` wherever they accept a column name. Comparisons,
+/// `in_` and `is` then only compile with values convertible into `V`. `#[derive(PostgrestTable)]`
+/// generates one associated constant per field, e.g. `User::username`.
+///
+/// # Example
+///
+/// ```
+/// use postgrest::{Column, Postgrest};
+///
+/// struct User;
+///
+/// #[allow(non_upper_case_globals)]
+/// impl User {
+///     const age: Column<User, i32> = Column::new("age");
+///     const username: Column<User, String> = Column::new("username");
+/// }
+///
+/// let client = Postgrest::new("https://your.postgrest.endpoint");
+/// client
+///     .from("users")
+///     .eq(User::username, "soedirgo")
+///     .gte(User::age, 30)
+///     .in_(User::age, [30, 40])
+///     .order(User::age.desc());
+/// ```
+///
+/// Comparing a column with a value of the wrong type doesn't compile:
+///
+/// ```compile_fail
+/// # use postgrest::{Column, Postgrest};
+/// # struct User;
+/// # #[allow(non_upper_case_globals)]
+/// # impl User {
+/// #     const age: Column<User, i32> = Column::new("age");
+/// # }
+/// # let client = Postgrest::new("https://your.postgrest.endpoint");
+/// client.from("users").eq(User::age, "thirty");
+/// ```
+///
+/// Nor does a list of values of the wrong type:
+///
+/// ```compile_fail
+/// # use postgrest::{Column, Postgrest};
+/// # struct User;
+/// # #[allow(non_upper_case_globals)]
+/// # impl User {
+/// #     const age: Column<User, i32> = Column::new("age");
+/// # }
+/// # let client = Postgrest::new("https://your.postgrest.endpoint");
+/// client.from("users").in_(User::age, ["thirty", "forty"]);
+/// ```
+pub struct Column<T, V> {
+    name: &'static str,
+    _marker: PhantomData<fn() -> (T, V)>,
+}
+
+impl<T, V> Column<T, V> {
+    /// Creates a reference to the column `name`.
+    pub const fn new(name: &'static str) -> Self {
+        Column {
+            name,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The column name.
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Orders by this column, ascending.
+    pub fn asc(self) -> Order {
+        Order::asc(self)
+    }
+
+    /// Orders by this column, descending.
+    pub fn desc(self) -> Order {
+        Order::desc(self)
+    }
+}
+
+impl<T, V> Clone for Column<T, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, V> Copy for Column<T, V> {}
+
+impl<T, V> fmt::Debug for Column<T, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Column").field(&self.name).finish()
+    }
+}
+
+/// Anything that names a column: strings, or a typed [`Column`].
+pub trait ColumnName {
+    /// The column name.
+    fn column_name(&self) -> &str;
+}
+
+impl<S> ColumnName for S
+where
+    S: AsRef<str>,
+{
+    fn column_name(&self) -> &str {
+        self.as_ref()
+    }
+}
+
+impl<T, V> ColumnName for Column<T, V> {
+    fn column_name(&self) -> &str {
+        self.name
+    }
+}
+
+/// A column that can be compared with values of type `U`.
+///
+/// Column names given as strings are compared with string values, as
+/// before; a [`Column<T, V>`] with anything convertible into `V`.
+pub trait Filterable<U>: ColumnName {
+    /// Renders `value` for a filter on this column.
+    fn filter_value(value: U) -> String;
+}
+
+impl<S, U> Filterable<U> for S
+where
+    S: AsRef<str>,
+    U: AsRef<str>,
+{
+    fn filter_value(value: U) -> String {
+        value.as_ref().to_string()
+    }
+}
+
+impl<T, V, U> Filterable<U> for Column<T, V>
+where
+    V: FilterValue,
+    U: Into<V>,
+{
+    fn filter_value(value: U) -> String {
+        value.into().to_filter_value()
+    }
+}
+
+/// A Rust value that can appear in a PostgREST filter.
+///
+/// Implement it for your own column types, e.g. UUIDs or timestamps.
+pub trait FilterValue {
+    /// Renders the value as PostgREST expects it in a query string.
+    fn to_filter_value(&self) -> String;
+}
+
+macro_rules! display_filter_value {
+    ($($ty:ty),*) => {
+        $(
+            impl FilterValue for $ty {
+                fn to_filter_value(&self) -> String {
+                    self.to_string()
+                }
+            }
+        )*
+    };
+}
+
+display_filter_value!(
+    String, bool, char, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64
+);
+
+impl<V> FilterValue for Option<V>
+where
+    V: FilterValue,
+{
+    fn to_filter_value(&self) -> String {
+        match self {
+            Some(value) => value.to_filter_value(),
+            None => "null".to_string(),
+        }
+    }
+}
+
+impl FilterValue for serde_json::Value {
+    fn to_filter_value(&self) -> String {
+        match self {
+            serde_json::Value::String(s) => s.clone(),
+            value => value.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct User;
+
+    #[allow(non_upper_case_globals)]
+    impl User {
+        const age: Column<User, Option<i32>> = Column::new("age");
+    }
+
+    #[test]
+    fn renders_typed_values() {
+        assert_eq!(User::age.column_name(), "age");
+        assert_eq!(<Column<User, Option<i32>>>::filter_value(30), "30");
+        assert_eq!(<Column<User, Option<i32>>>::filter_value(None), "null");
+        assert_eq!(<&str>::filter_value("thirty"), "thirty");
+        assert_eq!(User::age.desc().to_string(), "age.desc");
+    }
+
+    #[test]
+    fn filters_typed_lists() {
+        let builder =
+            crate::Builder::new("http://localhost:3000/users", None, Default::default(), ())
+                .in_(User::age, [Some(30), None])
+                .is(User::age, None)
+                .in_("name", ["a", "b"]);
+        assert_eq!(
+            builder.queries,
+            [
+                ("age".to_string(), "in.(30,null)".to_string()),
+                ("age".to_string(), "is.null".to_string()),
+                ("name".to_string(), "in.(a,b)".to_string()),
+            ]
+        );
+    }
+}
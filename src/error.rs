@@ -0,0 +1,87 @@
+use std::fmt;
+
+use http::StatusCode;
+
+/// Errors returned when executing a request.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// `reqwest` failed to send the request or to read the response.
+    #[cfg(feature = "reqwest")]
+    Http(reqwest::Error),
+    /// A custom [`Transport`](crate::Transport) failed to send the request.
+    Transport(Box<dyn std::error::Error + Send + Sync>),
+    /// PostgREST answered with an error status.
+    Status { status: StatusCode, body: String },
+    /// The response body isn't the JSON that was expected.
+    Decode(serde_json::Error),
+    /// A row couldn't be serialized to JSON. The request isn't sent.
+    Encode(serde_json::Error),
+    /// [`Builder::range`](crate::Builder::range) was given a `high` bound
+    /// below `low`. The request isn't sent.
+    InvalidRange { low: usize, high: usize },
+    /// A single row was expected, but none matched.
+    NoRows,
+    /// At most one row was expected, but several matched.
+    MultipleRows,
+    /// A request sent through a [`MockTransport`](crate::mock::MockTransport)
+    /// matched no expectation. Never retried.
+    #[cfg(feature = "mock")]
+    Unmatched(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "reqwest")]
+            Error::Http(err) => err.fmt(f),
+            Error::Transport(err) => write!(f, "transport error: {}", err),
+            Error::Status { status, body } => write!(f, "PostgREST returned {}: {}", status, body),
+            Error::Decode(err) => write!(f, "invalid response body: {}", err),
+            Error::Encode(err) => write!(f, "failed to serialize row: {}", err),
+            Error::InvalidRange { low, high } => {
+                write!(
+                    f,
+                    "invalid range {}-{}: upper bound is below lower bound",
+                    low, high
+                )
+            }
+            Error::NoRows => f.write_str("expected a single row, found none"),
+            Error::MultipleRows => f.write_str("expected at most one row, found several"),
+            #[cfg(feature = "mock")]
+            Error::Unmatched(request) => {
+                write!(f, "no mock expectation matches request:\n{}", request)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "reqwest")]
+            Error::Http(err) => Some(err),
+            Error::Transport(err) => Some(err.as_ref()),
+            Error::Status { .. }
+            | Error::InvalidRange { .. }
+            | Error::NoRows
+            | Error::MultipleRows => None,
+            #[cfg(feature = "mock")]
+            Error::Unmatched(_) => None,
+            Error::Decode(err) | Error::Encode(err) => Some(err),
+        }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Error::Http(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Decode(err)
+    }
+}
@@ -0,0 +1,252 @@
+//! Query plans, as returned by [`Builder::explain`](crate::Builder::explain).
+//!
+//! PostgREST only serves plans when `db-plan-enabled` is set.
+
+use serde_json::{Map, Value};
+
+/// The `EXPLAIN` options to run a request's query with.
+///
+/// # Example
+///
+/// ```
+/// use postgrest::ExplainOptions;
+///
+/// let options = ExplainOptions::new().analyze().buffers();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExplainOptions {
+    analyze: bool,
+    verbose: bool,
+    settings: bool,
+    buffers: bool,
+    wal: bool,
+}
+
+impl ExplainOptions {
+    /// Plans the query without running it.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs the query and reports actual times and row counts.
+    ///
+    /// # Note
+    ///
+    /// PostgREST rolls back analyzed writes, but they still run.
+    pub fn analyze(mut self) -> Self {
+        self.analyze = true;
+        self
+    }
+
+    /// Reports output columns and other details.
+    pub fn verbose(mut self) -> Self {
+        self.verbose = true;
+        self
+    }
+
+    /// Reports non-default configuration settings that affect planning.
+    pub fn settings(mut self) -> Self {
+        self.settings = true;
+        self
+    }
+
+    /// Reports buffer usage. Needs `analyze`.
+    pub fn buffers(mut self) -> Self {
+        self.buffers = true;
+        self
+    }
+
+    /// Reports WAL record generation. Needs `analyze`.
+    pub fn wal(mut self) -> Self {
+        self.wal = true;
+        self
+    }
+
+    /// The `Accept` header asking for the plan, in `format`, of a request
+    /// that would otherwise accept `accept`.
+    pub(crate) fn media_type(&self, format: &str, accept: &str) -> String {
+        let mut media_type = format!("application/vnd.pgrst.plan+{}", format);
+        if accept != "application/json" {
+            media_type.push_str(&format!("; for=\"{}\"", accept));
+        }
+        let options: Vec<&str> = [
+            (self.analyze, "analyze"),
+            (self.verbose, "verbose"),
+            (self.settings, "settings"),
+            (self.buffers, "buffers"),
+            (self.wal, "wal"),
+        ]
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, option)| *option)
+        .collect();
+        if !options.is_empty() {
+            media_type.push_str("; options=");
+            media_type.push_str(&options.join("|"));
+        }
+        media_type
+    }
+}
+
+/// The output of `EXPLAIN (FORMAT JSON)`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct Explain {
+    /// The root of the plan tree.
+    pub plan: Plan,
+    /// Milliseconds spent planning, with `analyze`.
+    pub planning_time: Option<f64>,
+    /// Milliseconds spent executing, with `analyze`.
+    pub execution_time: Option<f64>,
+}
+
+/// A node of a query plan.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct Plan {
+    /// E.g. `Seq Scan`, `Index Scan` or `Aggregate`.
+    pub node_type: String,
+    /// The scanned table, for scan nodes.
+    pub relation_name: Option<String>,
+    pub startup_cost: f64,
+    pub total_cost: f64,
+    /// The estimated number of rows.
+    pub plan_rows: f64,
+    /// The actual number of rows, with `analyze`.
+    pub actual_rows: Option<f64>,
+    /// The actual time in milliseconds, with `analyze`.
+    pub actual_total_time: Option<f64>,
+    /// Child nodes.
+    pub plans: Vec<Plan>,
+    /// Every other field of the node, e.g. `Filter` or `Index Name`.
+    pub details: Map<String, Value>,
+}
+
+impl Explain {
+    /// Parses the JSON plan PostgREST returns.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::explain::Explain;
+    ///
+    /// let explain = Explain::from_json(
+    ///     r#"[{"Plan": {
+    ///         "Node Type": "Aggregate",
+    ///         "Plans": [{"Node Type": "Seq Scan", "Relation Name": "users"}]
+    ///     }}]"#,
+    /// )
+    /// .unwrap();
+    /// let seq_scans: Vec<_> = explain
+    ///     .plan
+    ///     .nodes()
+    ///     .filter(|node| node.node_type == "Seq Scan")
+    ///     .collect();
+    /// assert_eq!(seq_scans[0].relation_name.as_deref(), Some("users"));
+    /// ```
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let doc: Value = serde_json::from_str(json)?;
+        let doc = match &doc {
+            Value::Array(items) => items.first().unwrap_or(&Value::Null),
+            doc => doc,
+        };
+        let root = doc.get("Plan").ok_or_else(|| {
+            <serde_json::Error as serde::de::Error>::custom("missing \"Plan\" in query plan")
+        })?;
+        Ok(Explain {
+            plan: plan(root),
+            planning_time: doc["Planning Time"].as_f64(),
+            execution_time: doc["Execution Time"].as_f64(),
+        })
+    }
+}
+
+impl Plan {
+    /// This node and all its descendants, depth first.
+    pub fn nodes(&self) -> impl Iterator<Item = &Plan> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            nodes.push(node);
+            stack.extend(node.plans.iter().rev());
+        }
+        nodes.into_iter()
+    }
+}
+
+fn plan(node: &Value) -> Plan {
+    let mut details = node.as_object().cloned().unwrap_or_default();
+    let mut take = |key: &str| details.remove(key).unwrap_or(Value::Null);
+    Plan {
+        node_type: take("Node Type").as_str().unwrap_or_default().to_string(),
+        relation_name: take("Relation Name").as_str().map(String::from),
+        startup_cost: take("Startup Cost").as_f64().unwrap_or_default(),
+        total_cost: take("Total Cost").as_f64().unwrap_or_default(),
+        plan_rows: take("Plan Rows").as_f64().unwrap_or_default(),
+        actual_rows: take("Actual Rows").as_f64(),
+        actual_total_time: take("Actual Total Time").as_f64(),
+        plans: take("Plans")
+            .as_array()
+            .map(|plans| plans.iter().map(plan).collect())
+            .unwrap_or_default(),
+        details,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_media_type() {
+        let options = ExplainOptions::new();
+        assert_eq!(
+            options.media_type("json", "application/json"),
+            "application/vnd.pgrst.plan+json"
+        );
+        let options = options.analyze().verbose().wal();
+        assert_eq!(
+            options.media_type("text", "application/vnd.pgrst.object+json"),
+            "application/vnd.pgrst.plan+text; for=\"application/vnd.pgrst.object+json\"; \
+             options=analyze|verbose|wal"
+        );
+    }
+
+    #[test]
+    fn parses_plan_tree() {
+        let explain = Explain::from_json(
+            r#"[{
+                "Plan": {
+                    "Node Type": "Limit",
+                    "Startup Cost": 0.0,
+                    "Total Cost": 1.5,
+                    "Plan Rows": 10,
+                    "Actual Rows": 3,
+                    "Plans": [
+                        {"Node Type": "Index Scan", "Relation Name": "users", "Index Name": "users_pkey"},
+                        {"Node Type": "Seq Scan", "Relation Name": "messages", "Filter": "(id > 1)"}
+                    ]
+                },
+                "Planning Time": 0.1,
+                "Execution Time": 0.2
+            }]"#,
+        )
+        .unwrap();
+        assert_eq!(explain.execution_time, Some(0.2));
+        assert_eq!(explain.plan.total_cost, 1.5);
+        assert_eq!(explain.plan.actual_rows, Some(3.0));
+        let nodes: Vec<&str> = explain
+            .plan
+            .nodes()
+            .map(|node| node.node_type.as_str())
+            .collect();
+        assert_eq!(nodes, ["Limit", "Index Scan", "Seq Scan"]);
+        assert_eq!(explain.plan.plans[1].details["Filter"], "(id > 1)");
+        assert!(!explain.plan.plans[1].details.contains_key("Node Type"));
+    }
+
+    #[test]
+    fn rejects_missing_plan() {
+        assert!(Explain::from_json("[{}]").is_err());
+    }
+}
@@ -1,6 +1,6 @@
-use crate::Builder;
+use crate::{Builder, ColumnName, Filterable};
 
-impl Builder {
+impl<C> Builder<C> {
     /// Finds all rows which doesn't satisfy the filter.
     ///
     /// # Example
@@ -21,11 +21,11 @@ impl Builder {
     pub fn not<T, U, V>(mut self, operator: T, column: U, filter: V) -> Self
     where
         T: AsRef<str>,
-        U: AsRef<str>,
+        U: ColumnName,
         V: AsRef<str>,
     {
         self.queries.push((
-            column.as_ref().into(),
+            column.column_name().into(),
             format!("not.{}.{}", operator.as_ref(), filter.as_ref()),
         ));
         self
@@ -117,11 +117,12 @@ impl Builder {
     /// ```
     pub fn eq<T, U>(mut self, column: T, filter: U) -> Self
     where
-        T: AsRef<str>,
-        U: AsRef<str>,
+        T: Filterable<U>,
     {
-        self.queries
-            .push((column.as_ref().into(), format!("eq.{}", filter.as_ref())));
+        self.queries.push((
+            column.column_name().into(),
+            format!("eq.{}", T::filter_value(filter)),
+        ));
         self
     }
 
@@ -145,11 +146,12 @@ impl Builder {
     /// ```
     pub fn neq<T, U>(mut self, column: T, filter: U) -> Self
     where
-        T: AsRef<str>,
-        U: AsRef<str>,
+        T: Filterable<U>,
     {
-        self.queries
-            .push((column.as_ref().into(), format!("neq.{}", filter.as_ref())));
+        self.queries.push((
+            column.column_name().into(),
+            format!("neq.{}", T::filter_value(filter)),
+        ));
         self
     }
 
@@ -173,11 +175,12 @@ impl Builder {
     /// ```
     pub fn gt<T, U>(mut self, column: T, filter: U) -> Self
     where
-        T: AsRef<str>,
-        U: AsRef<str>,
+        T: Filterable<U>,
     {
-        self.queries
-            .push((column.as_ref().into(), format!("gt.{}", filter.as_ref())));
+        self.queries.push((
+            column.column_name().into(),
+            format!("gt.{}", T::filter_value(filter)),
+        ));
         self
     }
 
@@ -201,11 +204,12 @@ impl Builder {
     /// ```
     pub fn gte<T, U>(mut self, column: T, filter: U) -> Self
     where
-        T: AsRef<str>,
-        U: AsRef<str>,
+        T: Filterable<U>,
     {
-        self.queries
-            .push((column.as_ref().into(), format!("gte.{}", filter.as_ref())));
+        self.queries.push((
+            column.column_name().into(),
+            format!("gte.{}", T::filter_value(filter)),
+        ));
         self
     }
 
@@ -229,11 +233,12 @@ impl Builder {
     /// ```
     pub fn lt<T, U>(mut self, column: T, filter: U) -> Self
     where
-        T: AsRef<str>,
-        U: AsRef<str>,
+        T: Filterable<U>,
     {
-        self.queries
-            .push((column.as_ref().into(), format!("lt.{}", filter.as_ref())));
+        self.queries.push((
+            column.column_name().into(),
+            format!("lt.{}", T::filter_value(filter)),
+        ));
         self
     }
 
@@ -257,11 +262,12 @@ impl Builder {
     /// ```
     pub fn lte<T, U>(mut self, column: T, filter: U) -> Self
     where
-        T: AsRef<str>,
-        U: AsRef<str>,
+        T: Filterable<U>,
     {
-        self.queries
-            .push((column.as_ref().into(), format!("lte.{}", filter.as_ref())));
+        self.queries.push((
+            column.column_name().into(),
+            format!("lte.{}", T::filter_value(filter)),
+        ));
         self
     }
 
@@ -292,12 +298,12 @@ impl Builder {
     /// ```
     pub fn like<T, U>(mut self, column: T, pattern: U) -> Self
     where
-        T: AsRef<str>,
+        T: ColumnName,
         U: Into<String>,
     {
         let pattern = pattern.into().replace('%', "*");
         self.queries
-            .push((column.as_ref().into(), format!("like.{}", pattern)));
+            .push((column.column_name().into(), format!("like.{}", pattern)));
         self
     }
 
@@ -328,12 +334,12 @@ impl Builder {
     /// ```
     pub fn ilike<T, U>(mut self, column: T, pattern: U) -> Self
     where
-        T: AsRef<str>,
+        T: ColumnName,
         U: Into<String>,
     {
         let pattern = pattern.into().replace('%', "*");
         self.queries
-            .push((column.as_ref().into(), format!("ilike.{}", pattern)));
+            .push((column.column_name().into(), format!("ilike.{}", pattern)));
         self
     }
 
@@ -357,11 +363,12 @@ impl Builder {
     /// ```
     pub fn is<T, U>(mut self, column: T, filter: U) -> Self
     where
-        T: AsRef<str>,
-        U: AsRef<str>,
+        T: Filterable<U>,
     {
-        self.queries
-            .push((column.as_ref().into(), format!("is.{}", filter.as_ref())));
+        self.queries.push((
+            column.column_name().into(),
+            format!("is.{}", T::filter_value(filter)),
+        ));
         self
     }
 
@@ -399,16 +406,15 @@ impl Builder {
     /// ```
     pub fn in_<T, U, V>(mut self, column: T, values: U) -> Self
     where
-        T: AsRef<str>,
+        T: Filterable<V>,
         U: IntoIterator<Item = V>,
-        V: AsRef<str>,
     {
         let mut values: String = values
             .into_iter()
-            .fold(String::new(), |a, s| a + s.as_ref() + ",");
+            .fold(String::new(), |a, s| a + &T::filter_value(s) + ",");
         values.pop();
         self.queries
-            .push((column.as_ref().into(), format!("in.({})", values)));
+            .push((column.column_name().into(), format!("in.({})", values)));
         self
     }
 
@@ -432,11 +438,13 @@ impl Builder {
     /// ```
     pub fn cs<T, U>(mut self, column: T, filter: U) -> Self
     where
-        T: AsRef<str>,
+        T: ColumnName,
         U: AsRef<str>,
     {
-        self.queries
-            .push((column.as_ref().into(), format!("cs.{}", filter.as_ref())));
+        self.queries.push((
+            column.column_name().into(),
+            format!("cs.{}", filter.as_ref()),
+        ));
         self
     }
 
@@ -460,11 +468,13 @@ impl Builder {
     /// ```
     pub fn cd<T, U>(mut self, column: T, filter: U) -> Self
     where
-        T: Into<String>,
+        T: ColumnName,
         U: AsRef<str>,
     {
-        self.queries
-            .push((column.into(), format!("cd.{}", filter.as_ref())));
+        self.queries.push((
+            column.column_name().into(),
+            format!("cd.{}", filter.as_ref()),
+        ));
         self
     }
 
@@ -488,10 +498,12 @@ impl Builder {
     /// ```
     pub fn sl<T>(mut self, column: T, range: (i64, i64)) -> Self
     where
-        T: Into<String>,
+        T: ColumnName,
     {
-        self.queries
-            .push((column.into(), format!("sl.({},{})", range.0, range.1)));
+        self.queries.push((
+            column.column_name().into(),
+            format!("sl.({},{})", range.0, range.1),
+        ));
         self
     }
 
@@ -515,10 +527,12 @@ impl Builder {
     /// ```
     pub fn sr<T>(mut self, column: T, range: (i64, i64)) -> Self
     where
-        T: Into<String>,
+        T: ColumnName,
     {
-        self.queries
-            .push((column.into(), format!("sr.({},{})", range.0, range.1)));
+        self.queries.push((
+            column.column_name().into(),
+            format!("sr.({},{})", range.0, range.1),
+        ));
         self
     }
 
@@ -542,10 +556,12 @@ impl Builder {
     /// ```
     pub fn nxl<T>(mut self, column: T, range: (i64, i64)) -> Self
     where
-        T: Into<String>,
+        T: ColumnName,
     {
-        self.queries
-            .push((column.into(), format!("nxl.({},{})", range.0, range.1)));
+        self.queries.push((
+            column.column_name().into(),
+            format!("nxl.({},{})", range.0, range.1),
+        ));
         self
     }
 
@@ -569,10 +585,12 @@ impl Builder {
     /// ```
     pub fn nxr<T>(mut self, column: T, range: (i64, i64)) -> Self
     where
-        T: Into<String>,
+        T: ColumnName,
     {
-        self.queries
-            .push((column.into(), format!("nxr.({},{})", range.0, range.1)));
+        self.queries.push((
+            column.column_name().into(),
+            format!("nxr.({},{})", range.0, range.1),
+        ));
         self
     }
 
@@ -596,10 +614,12 @@ impl Builder {
     /// ```
     pub fn adj<T>(mut self, column: T, range: (i64, i64)) -> Self
     where
-        T: Into<String>,
+        T: ColumnName,
     {
-        self.queries
-            .push((column.into(), format!("adj.({},{})", range.0, range.1)));
+        self.queries.push((
+            column.column_name().into(),
+            format!("adj.({},{})", range.0, range.1),
+        ));
         self
     }
 
@@ -623,11 +643,13 @@ impl Builder {
     /// ```
     pub fn ov<T, U>(mut self, column: T, filter: U) -> Self
     where
-        T: Into<String>,
+        T: ColumnName,
         U: AsRef<str>,
     {
-        self.queries
-            .push((column.into(), format!("ov.{}", filter.as_ref())));
+        self.queries.push((
+            column.column_name().into(),
+            format!("ov.{}", filter.as_ref()),
+        ));
         self
     }
 
@@ -651,7 +673,7 @@ impl Builder {
     /// ```
     pub fn fts<T, U>(mut self, column: T, tsquery: U, config: Option<&str>) -> Self
     where
-        T: Into<String>,
+        T: ColumnName,
         U: AsRef<str>,
     {
         let config = if let Some(conf) = config {
@@ -659,8 +681,10 @@ impl Builder {
         } else {
             String::new()
         };
-        self.queries
-            .push((column.into(), format!("fts{}.{}", config, tsquery.as_ref())));
+        self.queries.push((
+            column.column_name().into(),
+            format!("fts{}.{}", config, tsquery.as_ref()),
+        ));
         self
     }
 
@@ -684,7 +708,7 @@ impl Builder {
     /// ```
     pub fn plfts<T, U>(mut self, column: T, tsquery: U, config: Option<&str>) -> Self
     where
-        T: Into<String>,
+        T: ColumnName,
         U: AsRef<str>,
     {
         let config = if let Some(conf) = config {
@@ -693,7 +717,7 @@ impl Builder {
             String::new()
         };
         self.queries.push((
-            column.into(),
+            column.column_name().into(),
             format!("plfts{}.{}", config, tsquery.as_ref()),
         ));
         self
@@ -719,7 +743,7 @@ impl Builder {
     /// ```
     pub fn phfts<T, U>(mut self, column: T, tsquery: U, config: Option<&str>) -> Self
     where
-        T: Into<String>,
+        T: ColumnName,
         U: AsRef<str>,
     {
         let config = if let Some(conf) = config {
@@ -728,7 +752,7 @@ impl Builder {
             String::new()
         };
         self.queries.push((
-            column.into(),
+            column.column_name().into(),
             format!("phfts{}.{}", config, tsquery.as_ref()),
         ));
         self
@@ -754,7 +778,7 @@ impl Builder {
     /// ```
     pub fn wfts<T, U>(mut self, column: T, tsquery: U, config: Option<&str>) -> Self
     where
-        T: Into<String>,
+        T: ColumnName,
         U: AsRef<str>,
     {
         let config = if let Some(conf) = config {
@@ -763,7 +787,7 @@ impl Builder {
             String::new()
         };
         self.queries.push((
-            column.into(),
+            column.column_name().into(),
             format!("wfts{}.{}", config, tsquery.as_ref()),
         ));
         self
@@ -0,0 +1,473 @@
+//! A typed model of the OpenAPI document PostgREST serves at its root URL.
+//!
+//! Fetch it with [`Postgrest::introspect`](crate::Postgrest::introspect), or
+//! parse a saved document with [`ApiSchema::from_json`].
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::de::{Deserializer, MapAccess, Visitor};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+/// The relations and functions PostgREST exposes for one schema.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct ApiSchema {
+    /// Tables and views, sorted by name.
+    pub relations: Vec<Relation>,
+    /// Functions callable through `rpc`, sorted by name.
+    pub functions: Vec<Function>,
+}
+
+/// A table or view.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct Relation {
+    pub name: String,
+    pub description: Option<String>,
+    /// Columns, in table order.
+    pub columns: Vec<Column>,
+    /// Whether rows can be inserted, i.e. PostgREST accepts `POST`.
+    pub insertable: bool,
+    /// Whether rows can be updated, i.e. PostgREST accepts `PATCH`.
+    pub updatable: bool,
+    /// Whether rows can be deleted, i.e. PostgREST accepts `DELETE`.
+    pub deletable: bool,
+}
+
+impl Relation {
+    /// Looks up a column by name.
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+
+    /// The primary key columns.
+    pub fn primary_key(&self) -> impl Iterator<Item = &Column> {
+        self.columns.iter().filter(|column| column.primary_key)
+    }
+}
+
+/// A column of a [`Relation`].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct Column {
+    pub name: String,
+    /// The PostgreSQL type, e.g. `integer`, `text[]` or `public.user_status`.
+    pub data_type: String,
+    /// The JSON type PostgREST serializes the column as, e.g. `string`.
+    pub json_type: Option<String>,
+    /// Whether the column accepts `NULL`.
+    pub nullable: bool,
+    /// The default value or expression, e.g. `now()`.
+    pub default: Option<String>,
+    pub primary_key: bool,
+    pub foreign_key: Option<ForeignKey>,
+    /// The labels of an enum type.
+    pub enum_values: Vec<String>,
+    /// The column comment.
+    pub description: Option<String>,
+}
+
+/// The column a foreign key references.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForeignKey {
+    pub table: String,
+    pub column: String,
+}
+
+/// A function callable through `rpc`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct Function {
+    pub name: String,
+    pub description: Option<String>,
+    /// Arguments, sorted by name.
+    pub arguments: Vec<Argument>,
+}
+
+/// An argument of a [`Function`].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct Argument {
+    pub name: String,
+    /// The PostgreSQL type, e.g. `integer`.
+    pub data_type: String,
+    /// The JSON type the argument is passed as.
+    pub json_type: Option<String>,
+    /// Whether the argument has no default and must be passed.
+    pub required: bool,
+}
+
+impl ApiSchema {
+    /// Parses the OpenAPI (Swagger 2.0) document served by PostgREST.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::introspect::ApiSchema;
+    ///
+    /// let schema = ApiSchema::from_json(
+    ///     r#"{
+    ///         "paths": {"/users": {"get": {}}},
+    ///         "definitions": {
+    ///             "users": {
+    ///                 "required": ["username"],
+    ///                 "properties": {
+    ///                     "username": {
+    ///                         "format": "text",
+    ///                         "type": "string",
+    ///                         "description": "Note:\nThis is a Primary Key.<pk/>"
+    ///                     }
+    ///                 }
+    ///             }
+    ///         }
+    ///     }"#,
+    /// )
+    /// .unwrap();
+    /// let users = schema.relation("users").unwrap();
+    /// assert!(users.column("username").unwrap().primary_key);
+    /// ```
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let doc: Document = serde_json::from_str(json)?;
+        let paths = doc.paths;
+
+        let mut relations: Vec<Relation> = doc
+            .definitions
+            .iter()
+            .map(|(name, definition)| {
+                let path = &paths.get(&format!("/{}", name)).unwrap_or(&Value::Null);
+                relation(name, definition, path)
+            })
+            .collect();
+        relations.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut functions: Vec<Function> = paths
+            .iter()
+            .filter_map(|(path, item)| Some(function(path.strip_prefix("/rpc/")?, item)))
+            .collect();
+        functions.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(ApiSchema {
+            relations,
+            functions,
+        })
+    }
+
+    /// Looks up a table or view by name.
+    pub fn relation(&self, name: &str) -> Option<&Relation> {
+        self.relations.iter().find(|relation| relation.name == name)
+    }
+
+    /// Looks up a function by name.
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+}
+
+/// The parts of the document that are read.
+#[derive(Deserialize)]
+struct Document {
+    #[serde(default)]
+    paths: Map<String, Value>,
+    #[serde(default)]
+    definitions: BTreeMap<String, Definition>,
+}
+
+/// A definition, with its properties in document order: `serde_json::Map`
+/// sorts them, but columns should keep table order.
+#[derive(Deserialize)]
+struct Definition {
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    required: Vec<String>,
+    #[serde(default)]
+    properties: Properties,
+}
+
+#[derive(Default)]
+struct Properties(Vec<(String, Value)>);
+
+impl<'de> Deserialize<'de> for Properties {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct PropertiesVisitor;
+
+        impl<'de> Visitor<'de> for PropertiesVisitor {
+            type Value = Properties;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a map of properties")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Properties, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut properties = Vec::new();
+                while let Some(property) = map.next_entry()? {
+                    properties.push(property);
+                }
+                Ok(Properties(properties))
+            }
+        }
+
+        deserializer.deserialize_map(PropertiesVisitor)
+    }
+}
+
+fn relation(name: &str, definition: &Definition, path: &Value) -> Relation {
+    let required = &definition.required;
+    let columns = definition
+        .properties
+        .0
+        .iter()
+        .map(|(name, property)| column(name, property, required.contains(name)))
+        .collect();
+    Relation {
+        name: name.to_string(),
+        description: definition.description.clone(),
+        columns,
+        insertable: !path["post"].is_null(),
+        updatable: !path["patch"].is_null(),
+        deletable: !path["delete"].is_null(),
+    }
+}
+
+fn column(name: &str, property: &Value, required: bool) -> Column {
+    let notes = property["description"].as_str().unwrap_or_default();
+    // PostgREST appends machine-readable notes to the column comment, e.g.
+    // "Note:\nThis is a Foreign Key to `users.id`.<fk table='users' column='id'/>".
+    let description = notes
+        .split("\n\nNote:")
+        .next()
+        .filter(|comment| !comment.starts_with("Note:") && !comment.is_empty())
+        .map(String::from);
+    Column {
+        name: name.to_string(),
+        data_type: data_type(property),
+        json_type: string(&property["type"]),
+        nullable: !required,
+        default: match &property["default"] {
+            Value::Null => None,
+            Value::String(default) => Some(default.clone()),
+            default => Some(default.to_string()),
+        },
+        primary_key: notes.contains("<pk/>"),
+        foreign_key: foreign_key(notes),
+        enum_values: string_array(&property["enum"]),
+        description,
+    }
+}
+
+fn foreign_key(notes: &str) -> Option<ForeignKey> {
+    let tag = &notes[notes.find("<fk ")?..];
+    let tag = &tag[..tag.find("/>")?];
+    let attribute = |name: &str| {
+        let start = tag.find(&format!("{}='", name))? + name.len() + 2;
+        let len = tag[start..].find('\'')?;
+        Some(tag[start..start + len].to_string())
+    };
+    Some(ForeignKey {
+        table: attribute("table")?,
+        column: attribute("column")?,
+    })
+}
+
+fn function(name: &str, item: &Value) -> Function {
+    let post = &item["post"];
+    let body = post["parameters"]
+        .as_array()
+        .and_then(|parameters| parameters.iter().find(|p| p["in"] == "body"));
+    let mut arguments: Vec<Argument> = match body {
+        Some(body) => {
+            let schema = &body["schema"];
+            let required = string_array(&schema["required"]);
+            schema["properties"]
+                .as_object()
+                .map(|properties| {
+                    properties
+                        .iter()
+                        .map(|(name, property)| Argument {
+                            name: name.clone(),
+                            data_type: data_type(property),
+                            json_type: string(&property["type"]),
+                            required: required.contains(name),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+        None => Vec::new(),
+    };
+    arguments.sort_by(|a, b| a.name.cmp(&b.name));
+    Function {
+        name: name.to_string(),
+        description: string(&post["summary"]).or_else(|| string(&item["get"]["summary"])),
+        arguments,
+    }
+}
+
+fn data_type(property: &Value) -> String {
+    string(&property["format"])
+        .or_else(|| string(&property["type"]))
+        .unwrap_or_default()
+}
+
+fn string(value: &Value) -> Option<String> {
+    value.as_str().map(String::from)
+}
+
+fn string_array(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = r##"{
+        "swagger": "2.0",
+        "paths": {
+            "/": {"get": {}},
+            "/messages": {"get": {}, "post": {}, "patch": {}, "delete": {}},
+            "/online_users": {"get": {}},
+            "/rpc/get_status": {
+                "get": {"summary": "Status of a user"},
+                "post": {
+                    "summary": "Status of a user",
+                    "parameters": [
+                        {
+                            "in": "body",
+                            "name": "args",
+                            "required": true,
+                            "schema": {
+                                "required": ["name_param"],
+                                "properties": {
+                                    "name_param": {"format": "text", "type": "string"},
+                                    "verbose": {"format": "boolean", "type": "boolean"}
+                                },
+                                "type": "object"
+                            }
+                        },
+                        {"$ref": "#/parameters/preferParams"}
+                    ]
+                }
+            }
+        },
+        "definitions": {
+            "messages": {
+                "description": "Chat messages",
+                "required": ["id", "message", "username"],
+                "properties": {
+                    "id": {
+                        "default": "nextval('messages_id_seq'::regclass)",
+                        "description": "Note:\nThis is a Primary Key.<pk/>",
+                        "format": "bigint",
+                        "type": "integer"
+                    },
+                    "message": {"format": "text", "type": "string"},
+                    "username": {
+                        "description": "Author\n\nNote:\nThis is a Foreign Key to `users.username`.<fk table='users' column='username'/>",
+                        "format": "text",
+                        "type": "string"
+                    },
+                    "status": {
+                        "default": "ONLINE",
+                        "enum": ["ONLINE", "OFFLINE"],
+                        "format": "public.user_status",
+                        "type": "string"
+                    },
+                    "tags": {
+                        "format": "text[]",
+                        "type": "array",
+                        "items": {"type": "string"}
+                    }
+                },
+                "type": "object"
+            },
+            "online_users": {
+                "properties": {"username": {"format": "text", "type": "string"}},
+                "type": "object"
+            }
+        }
+    }"##;
+
+    #[test]
+    fn parses_relations() {
+        let schema = ApiSchema::from_json(DOC).unwrap();
+        let names: Vec<&str> = schema.relations.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["messages", "online_users"]);
+
+        let messages = schema.relation("messages").unwrap();
+        assert_eq!(messages.description.as_deref(), Some("Chat messages"));
+        assert!(messages.insertable && messages.updatable && messages.deletable);
+        let columns: Vec<&str> = messages.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(columns, ["id", "message", "username", "status", "tags"]);
+
+        let id = messages.column("id").unwrap();
+        assert_eq!(id.data_type, "bigint");
+        assert_eq!(id.json_type.as_deref(), Some("integer"));
+        assert!(id.primary_key && !id.nullable);
+        assert_eq!(
+            id.default.as_deref(),
+            Some("nextval('messages_id_seq'::regclass)")
+        );
+        assert_eq!(id.description, None);
+
+        let username = messages.column("username").unwrap();
+        assert_eq!(username.description.as_deref(), Some("Author"));
+        assert_eq!(
+            username.foreign_key,
+            Some(ForeignKey {
+                table: "users".into(),
+                column: "username".into()
+            })
+        );
+
+        let status = messages.column("status").unwrap();
+        assert!(status.nullable);
+        assert_eq!(status.enum_values, ["ONLINE", "OFFLINE"]);
+        assert_eq!(status.data_type, "public.user_status");
+
+        assert_eq!(messages.column("tags").unwrap().data_type, "text[]");
+        assert_eq!(messages.primary_key().count(), 1);
+
+        let online_users = schema.relation("online_users").unwrap();
+        assert!(!online_users.insertable && !online_users.updatable);
+    }
+
+    #[test]
+    fn parses_functions() {
+        let schema = ApiSchema::from_json(DOC).unwrap();
+        assert_eq!(schema.functions.len(), 1);
+        let get_status = schema.function("get_status").unwrap();
+        assert_eq!(get_status.description.as_deref(), Some("Status of a user"));
+        let arguments: Vec<(&str, &str, bool)> = get_status
+            .arguments
+            .iter()
+            .map(|a| (a.name.as_str(), a.data_type.as_str(), a.required))
+            .collect();
+        assert_eq!(
+            arguments,
+            [("name_param", "text", true), ("verbose", "boolean", false)]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_json() {
+        assert!(ApiSchema::from_json("<html>").is_err());
+    }
+}
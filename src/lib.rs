@@ -74,21 +74,67 @@
 //! [postgrest]: https://postgrest.org
 //! [readme]: https://github.com/supabase/postgrest-rs
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
 mod builder;
+mod bulk;
+mod column;
+mod error;
+pub mod explain;
 mod filter;
+pub mod introspect;
+mod middleware;
+#[cfg(feature = "mock")]
+pub mod mock;
+mod name;
+mod order;
+mod request;
+mod retry;
+mod stream;
+mod table;
+#[cfg(feature = "tracing")]
+mod trace;
+pub mod transport;
 
-pub use builder::Builder;
-use reqwest::header::{HeaderMap, HeaderValue, IntoHeaderName};
-use reqwest::Client;
+pub use builder::{Builder, Pagination};
+pub use bulk::{BulkOptions, BulkReport, ChunkReport};
+pub use column::{Column, ColumnName, FilterValue, Filterable};
+pub use error::Error;
+pub use explain::ExplainOptions;
+use http::header::{HeaderMap, HeaderValue, IntoHeaderName};
+pub use middleware::Middleware;
+use middleware::Middlewares;
+pub use name::{ParseNameError, QualifiedName, RelationName};
+pub use order::{Direction, Nulls, Order};
+#[cfg(feature = "derive")]
+pub use postgrest_derive::PostgrestTable;
+pub use request::RequestParts;
+pub use retry::RetryPolicy;
+use std::sync::Arc;
+pub use table::Table;
+pub use transport::{Response, SendFuture, SleepFuture, Transport};
+
+// Without the `reqwest` feature there is no default HTTP client, so clients
+// are built with `Postgrest::with_transport`.
+#[cfg(feature = "reqwest")]
+pub(crate) type Client = reqwest::Client;
+#[cfg(not(feature = "reqwest"))]
+pub(crate) type Client = Arc<dyn Transport>;
 
 #[derive(Clone, Debug)]
-pub struct Postgrest {
+pub struct Postgrest<C = Client> {
     url: String,
     schema: Option<String>,
     headers: HeaderMap,
-    client: Client,
+    retry: Option<RetryPolicy>,
+    pagination: Pagination,
+    middlewares: Middlewares,
+    #[cfg(feature = "tracing")]
+    redact_query_values: bool,
+    client: C,
 }
 
+#[cfg(feature = "reqwest")]
 impl Postgrest {
     /// Creates a Postgrest client.
     ///
@@ -100,6 +146,66 @@ impl Postgrest {
     /// let client = Postgrest::new("http://your.postgrest.endpoint");
     /// ```
     pub fn new<T>(url: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self::with_client(url, Client::new())
+    }
+}
+
+impl<T: Transport + Clone> Postgrest<T> {
+    /// Creates a Postgrest client that sends its requests through
+    /// `transport` instead of `reqwest`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::Postgrest;
+    ///
+    /// // Any `reqwest::Client` is a transport, e.g. one with a timeout.
+    /// let http = reqwest::Client::builder()
+    ///     .timeout(std::time::Duration::from_secs(5))
+    ///     .build()
+    ///     .unwrap();
+    /// let client = Postgrest::with_transport("http://your.postgrest.endpoint", http);
+    /// ```
+    pub fn with_transport<U>(url: U, transport: T) -> Self
+    where
+        U: Into<String>,
+    {
+        Self::with_client(url, transport)
+    }
+
+    /// Fetches the OpenAPI document PostgREST serves at its root and returns
+    /// the tables, views and functions of the current schema.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::Postgrest;
+    ///
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = Postgrest::new("http://your.postgrest.endpoint").schema("personal");
+    /// let schema = client.introspect().await?;
+    /// for relation in &schema.relations {
+    ///     println!("{}: {} columns", relation.name, relation.columns.len());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn introspect(&self) -> Result<introspect::ApiSchema, Error> {
+        let resp = self.openapi_request().execute().await?;
+        let status = resp.status();
+        let body = resp.text().await?;
+        if !status.is_success() {
+            return Err(Error::Status { status, body });
+        }
+        Ok(introspect::ApiSchema::from_json(&body)?)
+    }
+}
+
+impl<C: Clone> Postgrest<C> {
+    pub(crate) fn with_client<T>(url: T, client: C) -> Self
     where
         T: Into<String>,
     {
@@ -107,15 +213,22 @@ impl Postgrest {
             url: url.into(),
             schema: None,
             headers: HeaderMap::new(),
-            client: Client::new(),
+            retry: None,
+            pagination: Pagination::default(),
+            middlewares: Middlewares::default(),
+            #[cfg(feature = "tracing")]
+            redact_query_values: false,
+            client,
         }
     }
 
-    /// Switches the schema.
+    /// Switches the schema of every request made through this client.
     ///
     /// # Note
     ///
-    /// You can only switch schemas before you call `from` or `rpc`.
+    /// This only affects builders created afterwards. To use another schema
+    /// for a single request, see [`Builder::schema`] and
+    /// [`from_schema`](Self::from_schema).
     ///
     /// # Example
     ///
@@ -157,8 +270,88 @@ impl Postgrest {
         self
     }
 
+    /// Retries transient failures of every request made through this client
+    /// according to `policy`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::{Postgrest, RetryPolicy};
+    ///
+    /// let client = Postgrest::new("http://your.postgrest.endpoint")
+    ///     .retry(RetryPolicy::new().max_attempts(5));
+    /// ```
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Chooses how limits and offsets of every request made through this
+    /// client are sent: a `Range` header, the default, or `limit`/`offset`
+    /// query parameters.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::{Pagination, Postgrest};
+    ///
+    /// let client = Postgrest::new("http://your.postgrest.endpoint")
+    ///     .pagination(Pagination::Query);
+    /// let parts = client.from("users").offset(20).limit(10).to_request_parts();
+    /// assert!(parts.url.ends_with("?limit=10&offset=20"));
+    /// ```
+    pub fn pagination(mut self, pagination: Pagination) -> Self {
+        self.pagination = pagination;
+        self
+    }
+
+    /// Registers `middleware` to run around every request made through this
+    /// client. Middleware runs in the order it was registered.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::{Middleware, Postgrest, RequestParts};
+    ///
+    /// struct Logger;
+    ///
+    /// impl Middleware for Logger {
+    ///     fn on_request(&self, request: &mut RequestParts) {
+    ///         println!("{} {}", request.method, request.url);
+    ///     }
+    /// }
+    ///
+    /// let client = Postgrest::new("http://your.postgrest.endpoint").middleware(Logger);
+    /// ```
+    pub fn middleware<M>(mut self, middleware: M) -> Self
+    where
+        M: Middleware + 'static,
+    {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Replaces filter values with `?` in the query strings recorded on
+    /// tracing spans, keeping only columns and operators.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::Postgrest;
+    ///
+    /// let client = Postgrest::new("http://your.postgrest.endpoint").redact_query_values();
+    /// ```
+    #[cfg(feature = "tracing")]
+    pub fn redact_query_values(mut self) -> Self {
+        self.redact_query_values = true;
+        self
+    }
+
     /// Perform a table operation.
     ///
+    /// `table` is used verbatim; pass a [`QualifiedName`] to also select its
+    /// schema.
+    ///
     /// # Example
     ///
     /// ```
@@ -167,17 +360,65 @@ impl Postgrest {
     /// let client = Postgrest::new("http://your.postgrest.endpoint");
     /// client.from("table");
     /// ```
-    pub fn from<T>(&self, table: T) -> Builder
+    pub fn from<T>(&self, table: T) -> Builder<C>
+    where
+        T: RelationName,
+    {
+        let table = table.into_qualified_name();
+        let url = format!("{}/{}", self.url, table.path_segment());
+        self.builder(url, table.schema())
+    }
+
+    /// Perform a table operation on the relation modelled by `T`, selecting
+    /// exactly its columns unless [`Builder::select`] picks others.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "derive")]
+    /// # {
+    /// use postgrest::{Postgrest, PostgrestTable};
+    ///
+    /// #[derive(PostgrestTable)]
+    /// #[postgrest(table = "users", schema = "personal")]
+    /// struct User {
+    ///     #[postgrest(primary_key)]
+    ///     username: String,
+    ///     status: String,
+    /// }
+    ///
+    /// let client = Postgrest::new("http://your.postgrest.endpoint");
+    /// client.table::<User>().eq("status", "ONLINE");
+    /// # }
+    /// ```
+    pub fn table<T>(&self) -> Builder<C>
     where
+        T: Table,
+    {
+        let name = match T::SCHEMA {
+            Some(schema) => QualifiedName::new(schema, T::NAME),
+            None => QualifiedName::from(T::NAME),
+        };
+        self.from(name).table_select(table::select_list(T::COLUMNS))
+    }
+
+    /// Perform a table operation on `table` in `schema`, regardless of the
+    /// client's schema. Shorthand for `from(table).schema(schema)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use postgrest::Postgrest;
+    ///
+    /// let client = Postgrest::new("http://your.postgrest.endpoint");
+    /// client.from_schema("personal", "users");
+    /// ```
+    pub fn from_schema<S, T>(&self, schema: S, table: T) -> Builder<C>
+    where
+        S: Into<String>,
         T: AsRef<str>,
     {
-        let url = format!("{}/{}", self.url, table.as_ref());
-        Builder::new(
-            url,
-            self.schema.clone(),
-            self.headers.clone(),
-            self.client.clone(),
-        )
+        self.from(table.as_ref()).schema(schema)
     }
 
     /// Perform a stored procedure call.
@@ -190,19 +431,38 @@ impl Postgrest {
     /// let client = Postgrest::new("http://your.postgrest.endpoint");
     /// client.rpc("multiply", r#"{"a": 1, "b": 2}"#);
     /// ```
-    pub fn rpc<T, U>(&self, function: T, params: U) -> Builder
+    pub fn rpc<T, U>(&self, function: T, params: U) -> Builder<C>
     where
-        T: AsRef<str>,
+        T: RelationName,
         U: Into<String>,
     {
-        let url = format!("{}/rpc/{}", self.url, function.as_ref());
-        Builder::new(
+        let function = function.into_qualified_name();
+        let url = format!("{}/rpc/{}", self.url, function.path_segment());
+        self.builder(url, function.schema()).rpc(params)
+    }
+
+    pub(crate) fn openapi_request(&self) -> Builder<C> {
+        self.builder(self.url.clone(), None)
+            .header("Accept", "application/openapi+json")
+    }
+
+    fn builder(&self, url: String, schema: Option<&str>) -> Builder<C> {
+        let mut builder = Builder::new(
             url,
-            self.schema.clone(),
+            schema.map(String::from).or_else(|| self.schema.clone()),
             self.headers.clone(),
             self.client.clone(),
-        )
-        .rpc(params)
+        );
+        builder.middlewares = self.middlewares.clone();
+        builder = builder.pagination(self.pagination);
+        #[cfg(feature = "tracing")]
+        if self.redact_query_values {
+            builder = builder.redact_query_values();
+        }
+        match &self.retry {
+            Some(policy) => builder.retry(policy.clone()),
+            None => builder,
+        }
     }
 }
 
@@ -225,6 +485,77 @@ mod tests {
         );
     }
 
+    #[test]
+    fn qualified_name_selects_schema() {
+        let client = Postgrest::new(REST_URL).schema("public");
+        let name: QualifiedName = "personal.users".parse().unwrap();
+        let parts = client.from(name).select("*").to_request_parts();
+        assert_eq!(parts.url, "http://localhost:3000/users?select=*");
+        assert_eq!(parts.headers.get("Accept-Profile").unwrap(), "personal");
+
+        let name = QualifiedName::new("personal", "get status");
+        let parts = client.rpc(name, "{}").to_request_parts();
+        assert_eq!(parts.url, "http://localhost:3000/rpc/get%20status");
+        assert_eq!(parts.headers.get("Content-Profile").unwrap(), "personal");
+    }
+
+    #[test]
+    fn any_string_names_a_relation() {
+        use std::borrow::Cow;
+
+        let client = Postgrest::new(REST_URL);
+        let url = |parts: RequestParts| parts.url;
+        let name: &&str = &"users";
+        assert_eq!(
+            url(client.from(name).to_request_parts()),
+            "http://localhost:3000/users"
+        );
+        let name = Cow::from("users");
+        assert_eq!(
+            url(client.from(name).to_request_parts()),
+            "http://localhost:3000/users"
+        );
+        let name: Box<str> = "users".into();
+        assert_eq!(
+            url(client.from(name).to_request_parts()),
+            "http://localhost:3000/users"
+        );
+        let name = QualifiedName::new("public", "add");
+        assert_eq!(
+            url(client.rpc(&name, "{}").to_request_parts()),
+            "http://localhost:3000/rpc/add"
+        );
+    }
+
+    #[test]
+    fn encodes_unicode_names() {
+        let parts = Postgrest::new(REST_URL)
+            .from("café menu")
+            .eq("crème", "brûlée")
+            .to_request_parts();
+        assert_eq!(
+            parts.url,
+            "http://localhost:3000/caf%C3%A9%20menu?cr%C3%A8me=eq.br%C3%BBl%C3%A9e"
+        );
+    }
+
+    #[test]
+    fn openapi_request_uses_schema() {
+        let parts = Postgrest::new(REST_URL)
+            .schema("personal")
+            .openapi_request()
+            .to_request_parts();
+        assert_eq!(parts.url, REST_URL);
+        assert_eq!(parts.headers["Accept"], "application/openapi+json");
+        assert_eq!(parts.headers["Accept-Profile"], "personal");
+    }
+
+    #[test]
+    fn with_retry_policy() {
+        let client = Postgrest::new(REST_URL).retry(RetryPolicy::new().max_attempts(5));
+        assert!(client.retry.is_some());
+    }
+
     #[test]
     fn with_insert_header() {
         assert_eq!(
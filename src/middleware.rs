@@ -0,0 +1,112 @@
+use std::fmt;
+use std::sync::Arc;
+
+use http::{HeaderMap, StatusCode};
+
+use crate::{Error, RequestParts};
+
+/// Hooks that run around every request sent with `execute()`.
+///
+/// Middleware registered on a [`Postgrest`](crate::Postgrest) client runs in
+/// the order it was added. All hooks have empty default implementations, so
+/// you only need to implement the ones you care about.
+///
+/// # Example
+///
+/// ```
+/// use postgrest::{Middleware, Postgrest, RequestParts};
+/// use http::HeaderValue;
+///
+/// struct RequestId;
+///
+/// impl Middleware for RequestId {
+///     fn on_request(&self, request: &mut RequestParts) {
+///         request
+///             .headers
+///             .insert("X-Request-Id", HeaderValue::from_static("42"));
+///     }
+/// }
+///
+/// let client = Postgrest::new("https://your.postgrest.endpoint").middleware(RequestId);
+/// ```
+pub trait Middleware: Send + Sync {
+    /// Inspects or modifies the request before it is sent.
+    fn on_request(&self, _request: &mut RequestParts) {}
+
+    /// Observes the response received for `request`.
+    fn on_response(&self, _request: &RequestParts, _status: StatusCode, _headers: &HeaderMap) {}
+
+    /// Observes the error returned while sending `request`.
+    fn on_error(&self, _request: &RequestParts, _error: &Error) {}
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Middlewares(Vec<Arc<dyn Middleware>>);
+
+impl Middlewares {
+    pub(crate) fn push(&mut self, middleware: Arc<dyn Middleware>) {
+        self.0.push(middleware);
+    }
+
+    pub(crate) fn on_request(&self, request: &mut RequestParts) {
+        for middleware in &self.0 {
+            middleware.on_request(request);
+        }
+    }
+
+    pub(crate) fn on_response(
+        &self,
+        request: &RequestParts,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) {
+        for middleware in &self.0 {
+            middleware.on_response(request, status, headers);
+        }
+    }
+
+    pub(crate) fn on_error(&self, request: &RequestParts, error: &Error) {
+        for middleware in &self.0 {
+            middleware.on_error(request, error);
+        }
+    }
+}
+
+impl fmt::Debug for Middlewares {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{} middleware]", self.0.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{HeaderValue, Method};
+
+    struct Tag(&'static str);
+
+    impl Middleware for Tag {
+        fn on_request(&self, request: &mut RequestParts) {
+            request
+                .headers
+                .append("X-Tag", HeaderValue::from_static(self.0));
+        }
+    }
+
+    #[test]
+    fn runs_in_registration_order() {
+        let mut middlewares = Middlewares::default();
+        middlewares.push(Arc::new(Tag("first")));
+        middlewares.push(Arc::new(Tag("second")));
+        let mut request = RequestParts::new(
+            Method::GET,
+            "http://localhost:3000/table",
+            &[],
+            HeaderMap::new(),
+            None,
+        );
+        middlewares.on_request(&mut request);
+        let tags: Vec<_> = request.headers.get_all("X-Tag").iter().collect();
+        assert_eq!(tags, ["first", "second"]);
+    }
+}
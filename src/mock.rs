@@ -0,0 +1,465 @@
+//! An in-memory [`Transport`] for testing code that uses [`Postgrest`]
+//! without a running PostgREST.
+//!
+//! Register the requests you expect together with canned responses, run the
+//! code under test against a client built with
+//! [`Postgrest::with_transport`], then assert on the recorded calls.
+//!
+//! # Example
+//!
+//! ```
+//! use postgrest::mock::{MockResponse, MockTransport, When};
+//! use postgrest::Postgrest;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mock = MockTransport::new();
+//! mock.expect(
+//!     When::get("users").filter("username", "eq.supabot"),
+//!     MockResponse::json(r#"[{"status": "ONLINE"}]"#),
+//! );
+//!
+//! let client = Postgrest::with_transport("http://localhost:3000", mock.clone());
+//! let resp = client
+//!     .from("users")
+//!     .eq("username", "supabot")
+//!     .select("status")
+//!     .execute()
+//!     .await?;
+//! assert_eq!(resp.text().await?, r#"[{"status": "ONLINE"}]"#);
+//!
+//! assert_eq!(mock.calls().len(), 1);
+//! mock.verify();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Postgrest`]: crate::Postgrest
+//! [`Postgrest::with_transport`]: crate::Postgrest::with_transport
+
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use http::{
+    header::{HeaderMap, HeaderValue, IntoHeaderName, CONTENT_TYPE},
+    Method, StatusCode,
+};
+use url::Url;
+
+use crate::{transport, Error, RequestParts, SendFuture, SleepFuture, Transport};
+
+/// A [`Transport`] that answers requests from registered expectations
+/// instead of the network.
+///
+/// Clones share their expectations and recorded calls, so keep a clone around
+/// to inspect after handing one to the client.
+///
+/// A request that matches no expectation fails with [`Error::Unmatched`],
+/// which is never retried. Retries don't wait, so tests with a retry policy
+/// stay fast.
+#[derive(Clone, Debug, Default)]
+pub struct MockTransport {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    expectations: Vec<Expectation>,
+    calls: Vec<RequestParts>,
+}
+
+#[derive(Debug)]
+struct Expectation {
+    when: When,
+    response: MockResponse,
+    hits: usize,
+}
+
+impl MockTransport {
+    /// Creates a transport without expectations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers requests matching `when` with `response`. Expectations are
+    /// tried in registration order; the first one that matches and isn't
+    /// used up wins.
+    pub fn expect(&self, when: When, response: MockResponse) -> &Self {
+        self.state().expectations.push(Expectation {
+            when,
+            response,
+            hits: 0,
+        });
+        self
+    }
+
+    /// All requests sent so far, including unmatched ones, in order.
+    pub fn calls(&self) -> Vec<RequestParts> {
+        self.state().calls.clone()
+    }
+
+    /// Forgets the recorded calls, keeping the expectations.
+    pub fn clear_calls(&self) {
+        self.state().calls.clear();
+    }
+
+    /// Panics unless every expectation was matched, and exactly as often as
+    /// given by [`When::times`] where set.
+    pub fn verify(&self) {
+        let state = self.state();
+        let unmet: Vec<String> = state
+            .expectations
+            .iter()
+            .filter(|e| match e.when.times {
+                Some(times) => e.hits != times,
+                None => e.hits == 0,
+            })
+            .map(|e| format!("{:?} (matched {} times)", e.when, e.hits))
+            .collect();
+        if !unmet.is_empty() {
+            panic!("unmet mock expectations:\n{}", unmet.join("\n"));
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        // A panicking test shouldn't hide the state from other assertions.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn respond(&self, request: RequestParts) -> Result<transport::Response, Error> {
+        let mut state = self.state();
+        state.calls.push(request.clone());
+        let expectation = state
+            .expectations
+            .iter_mut()
+            .find(|e| e.when.times.is_none_or(|times| e.hits < times) && e.when.matches(&request));
+        match expectation {
+            Some(expectation) => {
+                expectation.hits += 1;
+                let response = &expectation.response;
+                Ok(transport::response(
+                    response.status,
+                    response.headers.clone(),
+                    response.body.clone(),
+                ))
+            }
+            None => Err(Error::Unmatched(request.to_string())),
+        }
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(&self, request: RequestParts) -> SendFuture<'_> {
+        let result = self.respond(request);
+        Box::pin(async move { result })
+    }
+
+    fn sleep(&self, _duration: Duration) -> SleepFuture<'_> {
+        Box::pin(async {})
+    }
+}
+
+/// Describes the requests an expectation matches. Only the parts that are
+/// set are compared.
+#[derive(Clone, Debug, Default)]
+pub struct When {
+    method: Option<Method>,
+    path: Option<String>,
+    queries: Vec<(String, String)>,
+    prefer: Vec<String>,
+    headers: Vec<(String, String)>,
+    body: Option<String>,
+    times: Option<usize>,
+}
+
+impl When {
+    /// Matches any request.
+    pub fn any() -> Self {
+        Self::default()
+    }
+
+    /// Matches requests to `table` with any method.
+    pub fn table<T>(table: T) -> Self
+    where
+        T: Into<String>,
+    {
+        When {
+            path: Some(table.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Matches requests calling the stored procedure `function`.
+    pub fn rpc<T>(function: T) -> Self
+    where
+        T: AsRef<str>,
+    {
+        Self::table(format!("rpc/{}", function.as_ref())).method(Method::POST)
+    }
+
+    /// Matches `GET` requests to `table`, i.e. selects.
+    pub fn get<T>(table: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self::table(table).method(Method::GET)
+    }
+
+    /// Matches `POST` requests to `table`, i.e. inserts and upserts.
+    pub fn post<T>(table: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self::table(table).method(Method::POST)
+    }
+
+    /// Matches `PATCH` requests to `table`, i.e. updates.
+    pub fn patch<T>(table: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self::table(table).method(Method::PATCH)
+    }
+
+    /// Matches `DELETE` requests to `table`.
+    pub fn delete<T>(table: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self::table(table).method(Method::DELETE)
+    }
+
+    /// Only matches requests with `method`.
+    pub fn method(mut self, method: Method) -> Self {
+        self.method = Some(method);
+        self
+    }
+
+    /// Only matches requests with the query parameter `key=value`, e.g.
+    /// `filter("id", "eq.1")` or `filter("select", "*")`.
+    pub fn filter<T, U>(mut self, key: T, value: U) -> Self
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        self.queries.push((key.into(), value.into()));
+        self
+    }
+
+    /// Only matches requests whose `Prefer` header includes `preference`,
+    /// e.g. `"return=representation"`.
+    pub fn prefer<T>(mut self, preference: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.prefer.push(preference.into());
+        self
+    }
+
+    /// Only matches requests with the header `name: value`.
+    pub fn header<T, U>(mut self, name: T, value: U) -> Self
+    where
+        T: AsRef<str>,
+        U: Into<String>,
+    {
+        self.headers
+            .push((name.as_ref().to_ascii_lowercase(), value.into()));
+        self
+    }
+
+    /// Only matches requests with exactly this body.
+    pub fn body<T>(mut self, body: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Stops matching after `times` requests, so that a later expectation
+    /// can answer subsequent ones. [`MockTransport::verify`] then checks the
+    /// expectation was matched exactly `times` times.
+    pub fn times(mut self, times: usize) -> Self {
+        self.times = Some(times);
+        self
+    }
+
+    fn matches(&self, request: &RequestParts) -> bool {
+        if self.method.as_ref().is_some_and(|m| *m != request.method) {
+            return false;
+        }
+        let url = match Url::parse(&request.url) {
+            Ok(url) => url,
+            Err(_) => return false,
+        };
+        if let Some(path) = &self.path {
+            let suffix = format!("/{}", path.trim_start_matches('/'));
+            let decoded = percent_encoding::percent_decode_str(url.path()).decode_utf8_lossy();
+            if !decoded.ends_with(&suffix) {
+                return false;
+            }
+        }
+        let queries: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        if !self.queries.iter().all(|query| queries.contains(query)) {
+            return false;
+        }
+        let prefer: Vec<&str> = request
+            .headers
+            .get_all("Prefer")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect();
+        if !self.prefer.iter().all(|p| prefer.contains(&p.as_str())) {
+            return false;
+        }
+        let has_header = |(name, value): &(String, String)| {
+            request
+                .headers
+                .get_all(name.as_str())
+                .iter()
+                .any(|v| v.to_str().is_ok_and(|v| v == value))
+        };
+        if !self.headers.iter().all(has_header) {
+            return false;
+        }
+        self.body.is_none() || self.body == request.body
+    }
+}
+
+/// A canned response returned by [`MockTransport`].
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: String,
+}
+
+impl MockResponse {
+    /// Creates an empty response with `status`.
+    pub fn new(status: StatusCode) -> Self {
+        MockResponse {
+            status,
+            headers: HeaderMap::new(),
+            body: String::new(),
+        }
+    }
+
+    /// Creates a `200 OK` response with a JSON body.
+    pub fn json<T>(body: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self::new(StatusCode::OK)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+    }
+
+    /// Sets the status.
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Adds a header.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` isn't a valid header value.
+    pub fn header(mut self, name: impl IntoHeaderName, value: impl AsRef<str>) -> Self {
+        let value = HeaderValue::from_str(value.as_ref()).expect("invalid header value");
+        self.headers.append(name, value);
+        self
+    }
+
+    /// Sets the body.
+    pub fn body<T>(mut self, body: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.body = body.into();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Postgrest;
+
+    const REST_URL: &str = "http://localhost:3000";
+
+    #[tokio::test]
+    async fn answers_matching_request() {
+        let mock = MockTransport::new();
+        mock.expect(
+            When::post("users")
+                .prefer("return=representation")
+                .body(r#"{"username":"soedirgo"}"#),
+            MockResponse::json(r#"[{"id":1}]"#).status(StatusCode::CREATED),
+        );
+        let client = Postgrest::with_transport(REST_URL, mock.clone());
+        let resp = client
+            .from("users")
+            .insert(r#"{"username":"soedirgo"}"#)
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert_eq!(resp.text().await.unwrap(), r#"[{"id":1}]"#);
+        mock.verify();
+    }
+
+    #[tokio::test]
+    async fn unmatched_request_fails_and_is_recorded() {
+        let mock = MockTransport::new();
+        mock.expect(
+            When::get("users").filter("id", "eq.1"),
+            MockResponse::json("[]"),
+        );
+        let client = Postgrest::with_transport(REST_URL, mock.clone());
+        let result = client.from("users").eq("id", "2").execute().await;
+        assert!(matches!(result, Err(Error::Unmatched(_))));
+        let calls = mock.calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].url, "http://localhost:3000/users?id=eq.2");
+    }
+
+    #[tokio::test]
+    async fn unmatched_request_is_not_retried() {
+        let mock = MockTransport::new();
+        let client = Postgrest::with_transport(REST_URL, mock.clone())
+            .retry(crate::RetryPolicy::new().max_attempts(3));
+        let result = client.from("users").execute().await;
+        assert!(matches!(result, Err(Error::Unmatched(_))));
+        assert_eq!(mock.calls().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn times_limits_matches() {
+        let mock = MockTransport::new();
+        mock.expect(
+            When::rpc("next").times(1),
+            MockResponse::new(StatusCode::SERVICE_UNAVAILABLE),
+        )
+        .expect(When::rpc("next"), MockResponse::json("1"));
+        let client = Postgrest::with_transport(REST_URL, mock.clone());
+        let first = client.rpc("next", "{}").execute().await.unwrap();
+        let second = client.rpc("next", "{}").execute().await.unwrap();
+        assert_eq!(first.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(second.status(), StatusCode::OK);
+        mock.verify();
+    }
+
+    #[test]
+    #[should_panic(expected = "unmet mock expectations")]
+    fn verify_panics_on_unused_expectation() {
+        let mock = MockTransport::new();
+        mock.expect(
+            When::delete("users"),
+            MockResponse::new(StatusCode::NO_CONTENT),
+        );
+        mock.verify();
+    }
+}
@@ -0,0 +1,265 @@
+use std::fmt;
+use std::str::FromStr;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+
+// Characters that can't appear verbatim in a URL path segment.
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// A table, view or function name, optionally qualified with its schema.
+///
+/// Plain strings convert into a `QualifiedName` as-is, so `"my.table"` names
+/// a relation with a dot in its name. Parse the string to split off the
+/// schema instead:
+///
+/// # Example
+///
+/// ```
+/// use postgrest::{Postgrest, QualifiedName};
+///
+/// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let name: QualifiedName = "personal.users".parse()?;
+/// assert_eq!(name.schema(), Some("personal"));
+/// assert_eq!(name.name(), "users");
+///
+/// // Sends `Accept-Profile: personal`.
+/// let client = Postgrest::new("https://your.postgrest.endpoint");
+/// client.from(name).select("*");
+///
+/// // Quote identifiers containing dots.
+/// let name: QualifiedName = r#"personal."user.v2""#.parse()?;
+/// assert_eq!(name.name(), "user.v2");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct QualifiedName {
+    schema: Option<String>,
+    name: String,
+}
+
+impl QualifiedName {
+    /// Creates a name in `schema`.
+    pub fn new<S, T>(schema: S, name: T) -> Self
+    where
+        S: Into<String>,
+        T: Into<String>,
+    {
+        QualifiedName {
+            schema: Some(schema.into()),
+            name: name.into(),
+        }
+    }
+
+    /// The schema, if the name is qualified.
+    pub fn schema(&self) -> Option<&str> {
+        self.schema.as_deref()
+    }
+
+    /// The unqualified name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The name, percent-encoded for use as a URL path segment.
+    pub(crate) fn path_segment(&self) -> String {
+        utf8_percent_encode(&self.name, PATH_SEGMENT).to_string()
+    }
+}
+
+impl From<&str> for QualifiedName {
+    fn from(name: &str) -> Self {
+        QualifiedName {
+            schema: None,
+            name: name.to_string(),
+        }
+    }
+}
+
+impl From<&String> for QualifiedName {
+    fn from(name: &String) -> Self {
+        Self::from(name.as_str())
+    }
+}
+
+impl From<String> for QualifiedName {
+    fn from(name: String) -> Self {
+        QualifiedName { schema: None, name }
+    }
+}
+
+/// Anything that names a table, view or function: a string, taken verbatim,
+/// or a [`QualifiedName`].
+pub trait RelationName {
+    /// Converts the name into a [`QualifiedName`].
+    fn into_qualified_name(self) -> QualifiedName;
+}
+
+impl<S> RelationName for S
+where
+    S: AsRef<str>,
+{
+    fn into_qualified_name(self) -> QualifiedName {
+        QualifiedName::from(self.as_ref())
+    }
+}
+
+impl RelationName for QualifiedName {
+    fn into_qualified_name(self) -> QualifiedName {
+        self
+    }
+}
+
+impl RelationName for &QualifiedName {
+    fn into_qualified_name(self) -> QualifiedName {
+        self.clone()
+    }
+}
+
+/// Splits `schema.name`. Either part may be double-quoted, with `""`
+/// standing for a literal quote, to include dots.
+impl FromStr for QualifiedName {
+    type Err = ParseNameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut chars = s.chars().peekable();
+        loop {
+            let mut part = String::new();
+            if chars.peek() == Some(&'"') {
+                chars.next();
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            part.push('"');
+                        }
+                        Some('"') => break,
+                        Some(c) => part.push(c),
+                        None => return Err(ParseNameError::new(s, "unterminated quote")),
+                    }
+                }
+                if !matches!(chars.peek(), None | Some('.')) {
+                    return Err(ParseNameError::new(s, "unexpected character after quote"));
+                }
+            } else {
+                while let Some(&c) = chars.peek() {
+                    if c == '.' {
+                        break;
+                    }
+                    if c == '"' {
+                        return Err(ParseNameError::new(s, "unexpected quote"));
+                    }
+                    part.push(c);
+                    chars.next();
+                }
+            }
+            if part.is_empty() {
+                return Err(ParseNameError::new(s, "empty identifier"));
+            }
+            parts.push(part);
+            if chars.next().is_none() {
+                break;
+            }
+        }
+
+        let mut parts = parts.into_iter();
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(name), None, _) => Ok(QualifiedName { schema: None, name }),
+            (Some(schema), Some(name), None) => Ok(QualifiedName::new(schema, name)),
+            _ => Err(ParseNameError::new(s, "too many dots")),
+        }
+    }
+}
+
+/// Renders the name as a quoted, qualified identifier, e.g.
+/// `"personal"."users"`.
+impl fmt::Display for QualifiedName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(schema) = &self.schema {
+            write!(f, "\"{}\".", schema.replace('"', "\"\""))?;
+        }
+        write!(f, "\"{}\"", self.name.replace('"', "\"\""))
+    }
+}
+
+/// Error returned when parsing an invalid [`QualifiedName`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseNameError {
+    input: String,
+    reason: &'static str,
+}
+
+impl ParseNameError {
+    fn new(input: &str, reason: &'static str) -> Self {
+        ParseNameError {
+            input: input.to_string(),
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for ParseNameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid name {:?}: {}", self.input, self.reason)
+    }
+}
+
+impl std::error::Error for ParseNameError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_qualified_names() {
+        assert_eq!(
+            "personal.users".parse(),
+            Ok(QualifiedName::new("personal", "users"))
+        );
+        assert_eq!("users".parse(), Ok(QualifiedName::from("users")));
+        assert_eq!(
+            r#""my schema"."a.b""c""#.parse(),
+            Ok(QualifiedName::new("my schema", r#"a.b"c"#))
+        );
+        assert_eq!(
+            "café.crème".parse(),
+            Ok(QualifiedName::new("café", "crème"))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_names() {
+        for name in ["", "a.", ".a", "a.b.c", r#""a"#, r#""a"b"#, r#"a"b""#] {
+            assert!(name.parse::<QualifiedName>().is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let name = QualifiedName::new("my schema", r#"a.b"c"#);
+        assert_eq!(name.to_string(), r#""my schema"."a.b""c""#);
+        assert_eq!(name.to_string().parse(), Ok(name));
+    }
+
+    #[test]
+    fn encodes_path_segment() {
+        assert_eq!(
+            QualifiedName::from("my table/v2?").path_segment(),
+            "my%20table%2Fv2%3F"
+        );
+        assert_eq!(QualifiedName::from("café").path_segment(), "caf%C3%A9");
+        assert_eq!(QualifiedName::from("a.b").path_segment(), "a.b");
+    }
+}
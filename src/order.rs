@@ -0,0 +1,203 @@
+use std::fmt;
+
+use crate::ColumnName;
+
+/// One term of an `order` parameter: a column, its direction and where
+/// `NULL`s go, optionally on an embedded (referenced) table.
+///
+/// Strings convert into an `Order` as-is, so `"username.desc,age_range"`
+/// keeps working.
+///
+/// # Example
+///
+/// ```
+/// use postgrest::{Order, Postgrest};
+///
+/// let client = Postgrest::new("https://your.postgrest.endpoint");
+/// let parts = client
+///     .from("countries")
+///     .select("name, cities(name)")
+///     .order(Order::desc("population").nulls_last())
+///     .order(Order::asc("name"))
+///     .order(Order::asc("name").referenced_table("cities"))
+///     .to_request_parts();
+/// assert!(parts
+///     .url
+///     .ends_with("order=population.desc.nullslast%2Cname.asc&cities.order=name.asc"));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Order {
+    column: String,
+    direction: Option<Direction>,
+    nulls: Option<Nulls>,
+    referenced_table: Option<String>,
+}
+
+/// The direction of an [`Order`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Asc,
+    Desc,
+}
+
+/// Where an [`Order`] puts `NULL`s.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Nulls {
+    First,
+    Last,
+}
+
+impl Order {
+    /// Orders by `column` in PostgreSQL's default direction, ascending.
+    pub fn new<T>(column: T) -> Self
+    where
+        T: ColumnName,
+    {
+        Order {
+            column: column.column_name().to_string(),
+            direction: None,
+            nulls: None,
+            referenced_table: None,
+        }
+    }
+
+    /// Orders by `column`, ascending.
+    pub fn asc<T>(column: T) -> Self
+    where
+        T: ColumnName,
+    {
+        Self::new(column).direction(Direction::Asc)
+    }
+
+    /// Orders by `column`, descending.
+    pub fn desc<T>(column: T) -> Self
+    where
+        T: ColumnName,
+    {
+        Self::new(column).direction(Direction::Desc)
+    }
+
+    /// Orders by a field inside the JSON `column`, e.g.
+    /// `Order::json("location", ["coordinates", "lat"])` orders by
+    /// `location->coordinates->>lat`.
+    pub fn json<T, I, S>(column: T, path: I) -> Self
+    where
+        T: ColumnName,
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut column = column.column_name().to_string();
+        let mut path = path.into_iter().peekable();
+        while let Some(key) = path.next() {
+            column.push_str(if path.peek().is_some() { "->" } else { "->>" });
+            column.push_str(key.as_ref());
+        }
+        Order::new(column)
+    }
+
+    /// Sets the direction.
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// Puts `NULL`s first.
+    pub fn nulls_first(mut self) -> Self {
+        self.nulls = Some(Nulls::First);
+        self
+    }
+
+    /// Puts `NULL`s last.
+    pub fn nulls_last(mut self) -> Self {
+        self.nulls = Some(Nulls::Last);
+        self
+    }
+
+    /// Orders the rows of the embedded `table` instead of the top-level ones.
+    pub fn referenced_table<T>(mut self, table: T) -> Self
+    where
+        T: Into<String>,
+    {
+        let table = table.into();
+        self.referenced_table = if table.is_empty() { None } else { Some(table) };
+        self
+    }
+
+    /// The query parameter this term belongs to, e.g. `cities.order`.
+    pub(crate) fn key(&self) -> String {
+        match &self.referenced_table {
+            Some(table) => format!("{}.order", table),
+            None => "order".to_string(),
+        }
+    }
+}
+
+/// Renders the term as it appears in the `order` parameter, e.g.
+/// `name.desc.nullslast`.
+impl fmt::Display for Order {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.column)?;
+        match self.direction {
+            Some(Direction::Asc) => f.write_str(".asc")?,
+            Some(Direction::Desc) => f.write_str(".desc")?,
+            None => {}
+        }
+        match self.nulls {
+            Some(Nulls::First) => f.write_str(".nullsfirst"),
+            Some(Nulls::Last) => f.write_str(".nullslast"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl From<&str> for Order {
+    fn from(order: &str) -> Self {
+        Order::new(order)
+    }
+}
+
+impl From<String> for Order {
+    fn from(order: String) -> Self {
+        Order::new(order)
+    }
+}
+
+impl From<&String> for Order {
+    fn from(order: &String) -> Self {
+        Order::new(order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_terms() {
+        assert_eq!(Order::new("id").to_string(), "id");
+        assert_eq!(
+            Order::asc("id").nulls_first().to_string(),
+            "id.asc.nullsfirst"
+        );
+        assert_eq!(
+            Order::desc("id").nulls_last().to_string(),
+            "id.desc.nullslast"
+        );
+        assert_eq!(
+            Order::json("data", ["a", "b"])
+                .direction(Direction::Desc)
+                .to_string(),
+            "data->a->>b.desc"
+        );
+    }
+
+    #[test]
+    fn keys_by_referenced_table() {
+        assert_eq!(Order::asc("id").key(), "order");
+        assert_eq!(
+            Order::asc("id").referenced_table("cities").key(),
+            "cities.order"
+        );
+        assert_eq!(Order::asc("id").referenced_table("").key(), "order");
+    }
+}
@@ -0,0 +1,193 @@
+use http::{
+    header::{HeaderMap, AUTHORIZATION},
+    Method,
+};
+use std::fmt;
+use url::form_urlencoded;
+
+/// The pieces of an HTTP request to PostgREST: method, URL (including the
+/// encoded query string), headers and body.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestParts {
+    pub method: Method,
+    /// Full URL, with the query string already percent-encoded.
+    pub url: String,
+    pub headers: HeaderMap,
+    pub body: Option<String>,
+}
+
+impl RequestParts {
+    pub(crate) fn new(
+        method: Method,
+        url: &str,
+        queries: &[(String, String)],
+        headers: HeaderMap,
+        body: Option<String>,
+    ) -> Self {
+        let mut url = url.to_string();
+        if !queries.is_empty() {
+            url.push(if url.contains('?') { '&' } else { '?' });
+            url.push_str(
+                &form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(queries)
+                    .finish(),
+            );
+        }
+        RequestParts {
+            method,
+            url,
+            headers,
+            body,
+        }
+    }
+
+    /// Renders a curl invocation sending this request. With `redact`, the
+    /// credentials in `Authorization` and `apikey` headers are masked.
+    pub(crate) fn to_curl(&self, redact: bool) -> String {
+        let mut curl = String::from("curl");
+        match self.method {
+            Method::GET => {}
+            Method::HEAD => curl.push_str(" --head"),
+            ref method => {
+                curl.push_str(" -X ");
+                curl.push_str(method.as_str());
+            }
+        }
+        curl.push(' ');
+        curl.push_str(&shell_quote(&self.url));
+        for (name, value) in &self.headers {
+            let value = value.to_str().unwrap_or_default();
+            let value = if redact && (name == AUTHORIZATION || name == "apikey") {
+                match value.split_once(' ') {
+                    Some((scheme, _)) => format!("{} [REDACTED]", scheme),
+                    None => "[REDACTED]".to_string(),
+                }
+            } else {
+                value.to_string()
+            };
+            curl.push_str(" \\\n  -H ");
+            curl.push_str(&shell_quote(&format!("{}: {}", name, value)));
+        }
+        if let Some(body) = &self.body {
+            curl.push_str(" \\\n  --data-raw ");
+            curl.push_str(&shell_quote(body));
+        }
+        curl
+    }
+
+    #[cfg(feature = "reqwest")]
+    pub(crate) fn into_request(self, client: &reqwest::Client) -> reqwest::RequestBuilder {
+        client
+            .request(self.method, self.url)
+            .headers(self.headers)
+            .body(self.body.unwrap_or_default())
+    }
+
+    #[cfg(feature = "blocking")]
+    pub(crate) fn into_blocking_request(
+        self,
+        client: &reqwest::blocking::Client,
+    ) -> reqwest::blocking::RequestBuilder {
+        client
+            .request(self.method, self.url)
+            .headers(self.headers)
+            .body(self.body.unwrap_or_default())
+    }
+}
+
+// Single-quotes `s` for POSIX shells.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Renders the request in HTTP/1.1 message style: the request line, one
+/// header per line, then the body after a blank line.
+impl fmt::Display for RequestParts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} {}", self.method, self.url)?;
+        for (name, value) in &self.headers {
+            writeln!(f, "{}: {}", name, value.to_str().unwrap_or("<binary>"))?;
+        }
+        if let Some(body) = &self.body {
+            write!(f, "\n{}", body)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_queries() {
+        let parts = RequestParts::new(
+            Method::GET,
+            "http://localhost:3000/users",
+            &[
+                ("select".into(), "*".into()),
+                ("name".into(), "eq.New Zealand".into()),
+            ],
+            HeaderMap::new(),
+            None,
+        );
+        assert_eq!(
+            parts.url,
+            "http://localhost:3000/users?select=*&name=eq.New+Zealand"
+        );
+    }
+
+    #[test]
+    fn display_as_http_message() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Prefer", "return=representation".parse().unwrap());
+        let parts = RequestParts::new(
+            Method::POST,
+            "http://localhost:3000/users",
+            &[],
+            headers,
+            Some(r#"{"username":"soedirgo"}"#.into()),
+        );
+        assert_eq!(
+            parts.to_string(),
+            "POST http://localhost:3000/users\n\
+             prefer: return=representation\n\
+             \n\
+             {\"username\":\"soedirgo\"}"
+        );
+    }
+
+    #[test]
+    fn curl_quotes_and_redacts() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", "Bearer s3cr3t".parse().unwrap());
+        let parts = RequestParts::new(
+            Method::POST,
+            "http://localhost:3000/rpc/greet",
+            &[],
+            headers,
+            Some(r#"{"name": "O'Brien"}"#.into()),
+        );
+        assert_eq!(
+            parts.to_curl(false),
+            "curl -X POST 'http://localhost:3000/rpc/greet' \\\n  \
+             -H 'authorization: Bearer s3cr3t' \\\n  \
+             --data-raw '{\"name\": \"O'\\''Brien\"}'"
+        );
+        assert!(parts
+            .to_curl(true)
+            .contains("-H 'authorization: Bearer [REDACTED]'"));
+    }
+
+    #[test]
+    fn no_queries_no_question_mark() {
+        let parts = RequestParts::new(
+            Method::GET,
+            "http://localhost:3000/users",
+            &[],
+            HeaderMap::new(),
+            None,
+        );
+        assert_eq!(parts.url, "http://localhost:3000/users");
+    }
+}
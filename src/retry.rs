@@ -0,0 +1,304 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, SystemTime};
+
+use http::{
+    header::{HeaderMap, RETRY_AFTER},
+    Method, StatusCode,
+};
+
+use crate::Error;
+
+/// Policy for retrying requests that fail transiently, e.g. with a 503 while
+/// PostgREST reloads its schema cache.
+///
+/// By default only `GET` and `HEAD` requests, and requests marked with
+/// [`Builder::idempotent`](crate::Builder::idempotent), are retried. The
+/// delays are waited with [`Transport::sleep`](crate::Transport::sleep).
+///
+/// # Example
+///
+/// ```
+/// use postgrest::{Postgrest, RetryPolicy};
+/// use std::time::Duration;
+///
+/// let client = Postgrest::new("https://your.postgrest.endpoint").retry(
+///     RetryPolicy::new()
+///         .max_attempts(5)
+///         .initial_backoff(Duration::from_millis(200)),
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+    statuses: Vec<StatusCode>,
+    connection_errors: bool,
+    respect_retry_after: bool,
+    mutations: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            jitter: true,
+            statuses: vec![
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            connection_errors: true,
+            respect_retry_after: true,
+            mutations: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a policy that makes up to 3 attempts, retrying on 429, 502,
+    /// 503, 504 and connection errors.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the total number of attempts, including the first one.
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Sets the delay before the first retry. The delay doubles after every
+    /// attempt.
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Caps the delay between attempts, including delays requested through
+    /// `Retry-After`.
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Randomizes each delay between half and the full backoff. Enabled by
+    /// default.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Replaces the set of response statuses that are retried.
+    pub fn statuses<T>(mut self, statuses: T) -> Self
+    where
+        T: IntoIterator<Item = StatusCode>,
+    {
+        self.statuses = statuses.into_iter().collect();
+        self
+    }
+
+    /// Whether to retry when the request couldn't be sent, e.g. on connection
+    /// resets, timeouts or errors of a custom transport. Enabled by default.
+    pub fn connection_errors(mut self, retry: bool) -> Self {
+        self.connection_errors = retry;
+        self
+    }
+
+    /// Whether to wait for the delay given by a `Retry-After` header, in
+    /// seconds or as an HTTP date, instead of the computed backoff. Enabled
+    /// by default.
+    pub fn respect_retry_after(mut self, respect: bool) -> Self {
+        self.respect_retry_after = respect;
+        self
+    }
+
+    /// Also retries INSERT, UPDATE, DELETE and RPC requests.
+    ///
+    /// # Note
+    ///
+    /// Only enable this if your writes are safe to repeat: a request that
+    /// timed out may still have been applied.
+    pub fn retry_mutations(mut self, retry: bool) -> Self {
+        self.mutations = retry;
+        self
+    }
+
+    pub(crate) fn allows(&self, method: &Method, idempotent: bool) -> bool {
+        self.max_attempts > 1
+            && (matches!(*method, Method::GET | Method::HEAD) || idempotent || self.mutations)
+    }
+
+    /// Decides whether to retry after the `attempt`-th (1-based) attempt
+    /// ended with `outcome`, and if so, how long to wait first.
+    pub(crate) fn next_delay(
+        &self,
+        attempt: u32,
+        outcome: Result<(StatusCode, &HeaderMap), &Error>,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        match outcome {
+            Ok((status, headers)) if self.statuses.contains(&status) => {
+                Some(self.delay(attempt, Some(headers)))
+            }
+            Err(error) if self.retries_error(error) => Some(self.delay(attempt, None)),
+            _ => None,
+        }
+    }
+
+    fn retries_error(&self, error: &Error) -> bool {
+        self.connection_errors
+            && match error {
+                #[cfg(feature = "reqwest")]
+                Error::Http(err) => err.is_connect() || err.is_timeout() || err.is_request(),
+                Error::Transport(_) => true,
+                _ => false,
+            }
+    }
+
+    /// Delay to wait after the `attempt`-th (1-based) attempt failed.
+    fn delay(&self, attempt: u32, headers: Option<&HeaderMap>) -> Duration {
+        if self.respect_retry_after {
+            let retry_after = headers
+                .and_then(|headers| headers.get(RETRY_AFTER))
+                .and_then(|value| value.to_str().ok())
+                .and_then(retry_after);
+            if let Some(delay) = retry_after {
+                return delay.min(self.max_backoff);
+            }
+        }
+
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff);
+        if self.jitter {
+            backoff / 2 + backoff.mul_f64(random_fraction() / 2.0)
+        } else {
+            backoff
+        }
+    }
+}
+
+/// Parses a `Retry-After` value: a number of seconds or an HTTP date.
+fn retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    // A date in the past means the request can be retried right away.
+    Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+// Good enough randomness for spreading out retries without pulling in `rand`.
+fn random_fraction() -> f64 {
+    let bits = RandomState::new().build_hasher().finish() >> 11;
+    bits as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    #[test]
+    fn only_reads_are_retried_by_default() {
+        let policy = RetryPolicy::new();
+        assert!(policy.allows(&Method::GET, false));
+        assert!(policy.allows(&Method::HEAD, false));
+        assert!(!policy.allows(&Method::POST, false));
+        assert!(!policy.allows(&Method::PATCH, false));
+        assert!(policy.allows(&Method::POST, true));
+        assert!(RetryPolicy::new()
+            .retry_mutations(true)
+            .allows(&Method::DELETE, false));
+    }
+
+    #[test]
+    fn single_attempt_disables_retries() {
+        assert!(!RetryPolicy::new()
+            .max_attempts(1)
+            .allows(&Method::GET, false));
+    }
+
+    #[test]
+    fn retries_listed_statuses_until_out_of_attempts() {
+        let policy = RetryPolicy::new().max_attempts(3);
+        let headers = HeaderMap::new();
+        let unavailable = Ok((StatusCode::SERVICE_UNAVAILABLE, &headers));
+        assert!(policy.next_delay(1, unavailable).is_some());
+        assert!(policy.next_delay(2, unavailable).is_some());
+        assert!(policy.next_delay(3, unavailable).is_none());
+        assert!(policy
+            .next_delay(1, Ok((StatusCode::BAD_REQUEST, &headers)))
+            .is_none());
+    }
+
+    #[test]
+    fn retries_transport_errors() {
+        let error = Error::Transport("connection reset".into());
+        assert!(RetryPolicy::new().next_delay(1, Err(&error)).is_some());
+        assert!(RetryPolicy::new()
+            .connection_errors(false)
+            .next_delay(1, Err(&error))
+            .is_none());
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let policy = RetryPolicy::new()
+            .jitter(false)
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_millis(350));
+        assert_eq!(policy.delay(1, None), Duration::from_millis(100));
+        assert_eq!(policy.delay(2, None), Duration::from_millis(200));
+        assert_eq!(policy.delay(3, None), Duration::from_millis(350));
+        assert_eq!(policy.delay(40, None), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn jitter_stays_within_backoff() {
+        let policy = RetryPolicy::new().initial_backoff(Duration::from_millis(100));
+        for _ in 0..100 {
+            let delay = policy.delay(1, None);
+            assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    fn honors_retry_after() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("2"));
+        let policy = RetryPolicy::new();
+        assert_eq!(policy.delay(1, Some(&headers)), Duration::from_secs(2));
+        assert_ne!(
+            policy.respect_retry_after(false).delay(1, Some(&headers)),
+            Duration::from_secs(2)
+        );
+    }
+
+    #[test]
+    fn honors_retry_after_dates() {
+        let policy = RetryPolicy::new().max_backoff(Duration::from_secs(60));
+        let mut headers = HeaderMap::new();
+        let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(30));
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(&date).unwrap());
+        let delay = policy.delay(1, Some(&headers));
+        assert!(delay > Duration::from_secs(28) && delay <= Duration::from_secs(30));
+
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(policy.delay(1, Some(&headers)), Duration::ZERO);
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+use bytes::Bytes;
+use futures_util::{stream, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+
+use crate::Error;
+
+/// Splits a response body into items as its chunks arrive.
+pub(crate) trait Decoder {
+    type Item;
+
+    /// Decodes the complete items in `chunk`, keeping partial ones for later.
+    fn push(&mut self, chunk: &[u8], out: &mut VecDeque<Self::Item>) -> Result<(), Error>;
+
+    /// Fails if the body ended in the middle of an item.
+    fn finish(&mut self, out: &mut VecDeque<Self::Item>) -> Result<(), Error>;
+}
+
+/// Decodes a stream of body chunks with `decoder`. The first error ends the
+/// stream.
+pub(crate) fn decode<S, D>(
+    body: S,
+    decoder: D,
+) -> impl Stream<Item = Result<D::Item, Error>> + Unpin
+where
+    S: Stream<Item = Result<Bytes, Error>>,
+    D: Decoder,
+{
+    let state = (Box::pin(body), decoder, VecDeque::new(), false);
+    Box::pin(stream::unfold(
+        state,
+        |(mut body, mut decoder, mut items, mut done)| async move {
+            loop {
+                if let Some(item) = items.pop_front() {
+                    return Some((Ok(item), (body, decoder, items, done)));
+                }
+                if done {
+                    return None;
+                }
+                let result = match body.next().await {
+                    Some(Ok(chunk)) => decoder.push(&chunk, &mut items),
+                    Some(Err(err)) => Err(err),
+                    None => {
+                        done = true;
+                        decoder.finish(&mut items)
+                    }
+                };
+                if let Err(err) = result {
+                    return Some((Err(err), (body, decoder, VecDeque::new(), true)));
+                }
+            }
+        },
+    ))
+}
+
+fn invalid(message: &str) -> Error {
+    Error::Decode(<serde_json::Error as serde::de::Error>::custom(message))
+}
+
+/// Yields the elements of a top-level JSON array, one row at a time.
+pub(crate) struct JsonArrayDecoder<R> {
+    element: Vec<u8>,
+    started: bool,
+    ended: bool,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    _row: PhantomData<fn() -> R>,
+}
+
+impl<R> JsonArrayDecoder<R> {
+    pub(crate) fn new() -> Self {
+        JsonArrayDecoder {
+            element: Vec::new(),
+            started: false,
+            ended: false,
+            depth: 0,
+            in_string: false,
+            escaped: false,
+            _row: PhantomData,
+        }
+    }
+}
+
+impl<R> JsonArrayDecoder<R>
+where
+    R: DeserializeOwned,
+{
+    fn flush(&mut self, out: &mut VecDeque<R>) -> Result<(), Error> {
+        if !self.element.iter().all(u8::is_ascii_whitespace) {
+            out.push_back(serde_json::from_slice(&self.element)?);
+        }
+        self.element.clear();
+        Ok(())
+    }
+}
+
+impl<R> Decoder for JsonArrayDecoder<R>
+where
+    R: DeserializeOwned,
+{
+    type Item = R;
+
+    fn push(&mut self, chunk: &[u8], out: &mut VecDeque<R>) -> Result<(), Error> {
+        for &byte in chunk {
+            if self.ended || !self.started {
+                match byte {
+                    b if b.is_ascii_whitespace() => {}
+                    b'[' if !self.ended => self.started = true,
+                    _ if self.ended => return Err(invalid("trailing data after JSON array")),
+                    _ => return Err(invalid("expected a JSON array")),
+                }
+                continue;
+            }
+            if self.in_string {
+                match byte {
+                    _ if self.escaped => self.escaped = false,
+                    b'\\' => self.escaped = true,
+                    b'"' => self.in_string = false,
+                    _ => {}
+                }
+            } else {
+                match byte {
+                    b',' if self.depth == 0 => {
+                        self.flush(out)?;
+                        continue;
+                    }
+                    b']' if self.depth == 0 => {
+                        self.flush(out)?;
+                        self.ended = true;
+                        continue;
+                    }
+                    b'"' => self.in_string = true,
+                    b'[' | b'{' => self.depth += 1,
+                    // Unbalanced input fails to deserialize, so don't underflow.
+                    b']' | b'}' => self.depth = self.depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+            self.element.push(byte);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, _out: &mut VecDeque<R>) -> Result<(), Error> {
+        if self.ended {
+            Ok(())
+        } else {
+            Err(invalid("unexpected end of JSON array"))
+        }
+    }
+}
+
+/// Yields the records of a CSV body, the header first.
+#[derive(Default)]
+pub(crate) struct CsvDecoder {
+    field: Vec<u8>,
+    record: Vec<String>,
+    in_quotes: bool,
+    // A quote inside a quoted field: either the closing quote or the first
+    // half of an escaped `""`.
+    quote: bool,
+    // The last byte was a `\r`, so a following `\n` ends nothing new.
+    carriage_return: bool,
+}
+
+impl CsvDecoder {
+    fn end_field(&mut self) -> Result<(), Error> {
+        let field = String::from_utf8(std::mem::take(&mut self.field))
+            .map_err(|_| invalid("CSV field isn't valid UTF-8"))?;
+        self.record.push(field);
+        Ok(())
+    }
+
+    fn end_record(&mut self, out: &mut VecDeque<Vec<String>>) -> Result<(), Error> {
+        self.end_field()?;
+        out.push_back(std::mem::take(&mut self.record));
+        Ok(())
+    }
+}
+
+impl Decoder for CsvDecoder {
+    type Item = Vec<String>;
+
+    fn push(&mut self, chunk: &[u8], out: &mut VecDeque<Vec<String>>) -> Result<(), Error> {
+        for &byte in chunk {
+            let carriage_return = std::mem::take(&mut self.carriage_return);
+            if self.quote {
+                self.quote = false;
+                if byte == b'"' {
+                    self.field.push(b'"');
+                    continue;
+                }
+                self.in_quotes = false;
+            }
+            if self.in_quotes {
+                match byte {
+                    b'"' => self.quote = true,
+                    _ => self.field.push(byte),
+                }
+                continue;
+            }
+            match byte {
+                b'"' if self.field.is_empty() => self.in_quotes = true,
+                b',' => self.end_field()?,
+                b'\n' if carriage_return => {}
+                b'\r' | b'\n' => {
+                    self.carriage_return = byte == b'\r';
+                    self.end_record(out)?;
+                }
+                _ => self.field.push(byte),
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut VecDeque<Vec<String>>) -> Result<(), Error> {
+        if self.in_quotes && !self.quote {
+            return Err(invalid("unexpected end of quoted CSV field"));
+        }
+        if !self.field.is_empty() || !self.record.is_empty() || self.quote {
+            self.quote = false;
+            self.end_record(out)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split<D: Decoder>(mut decoder: D, body: &str, chunk: usize) -> Result<Vec<D::Item>, Error> {
+        let mut out = VecDeque::new();
+        for chunk in body.as_bytes().chunks(chunk) {
+            decoder.push(chunk, &mut out)?;
+        }
+        decoder.finish(&mut out)?;
+        Ok(out.into())
+    }
+
+    #[test]
+    fn splits_json_array_across_chunks() {
+        let body = r#" [ {"id": 1, "tags": ["a]", "b,"]}, {"id": 2, "name": "q\"}"} ] "#;
+        for chunk in 1..body.len() {
+            let rows: Vec<serde_json::Value> = split(JsonArrayDecoder::new(), body, chunk).unwrap();
+            assert_eq!(rows.len(), 2);
+            assert_eq!(rows[0]["tags"][1], "b,");
+            assert_eq!(rows[1]["name"], "q\"}");
+        }
+        let rows: Vec<i32> = split(JsonArrayDecoder::new(), "[]", 1).unwrap();
+        assert!(rows.is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_json_arrays() {
+        assert!(split(JsonArrayDecoder::<i32>::new(), r#"{"id": 1}"#, 4).is_err());
+        assert!(split(JsonArrayDecoder::<i32>::new(), "[1, 2", 4).is_err());
+        assert!(split(JsonArrayDecoder::<i32>::new(), "[1] 2", 4).is_err());
+        assert!(split(JsonArrayDecoder::<i32>::new(), r#"[1, "a"]"#, 4).is_err());
+    }
+
+    #[test]
+    fn splits_csv_records_across_chunks() {
+        let body = "id,name\r\n1,\"Doe, \"\"J\"\"\"\r\n2,\"two\nlines\"\r\n3,";
+        for chunk in 1..body.len() {
+            let records = split(CsvDecoder::default(), body, chunk).unwrap();
+            assert_eq!(
+                records,
+                [
+                    vec!["id", "name"],
+                    vec!["1", "Doe, \"J\""],
+                    vec!["2", "two\nlines"],
+                    vec!["3", ""],
+                ]
+            );
+        }
+        assert!(split(CsvDecoder::default(), "1,\"open", 3).is_err());
+    }
+
+    #[tokio::test]
+    async fn decodes_byte_stream() {
+        let chunks = ["[1,", "2", ",3]"].map(|chunk| Ok(Bytes::from(chunk)));
+        let rows: Vec<i32> = decode(stream::iter(chunks), JsonArrayDecoder::<i32>::new())
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        assert_eq!(rows, [1, 2, 3]);
+
+        let chunks = [Ok(Bytes::from("[1,")), Err(invalid("reset"))];
+        let rows: Vec<Result<i32, Error>> = decode(stream::iter(chunks), JsonArrayDecoder::new())
+            .collect()
+            .await;
+        assert!(matches!(rows[..], [Ok(1), Err(_)]));
+    }
+}
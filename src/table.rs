@@ -0,0 +1,69 @@
+/// A Rust type modelling the rows of a table or view.
+///
+/// Usually implemented with `#[derive(PostgrestTable)]`, available with the
+/// `derive` feature. Pass the type to [`Postgrest::table`](crate::Postgrest::table)
+/// to query the relation.
+///
+/// # Example
+///
+/// ```
+/// use postgrest::{Postgrest, Table};
+///
+/// struct User {
+///     username: String,
+///     status: String,
+/// }
+///
+/// impl Table for User {
+///     const NAME: &'static str = "users";
+///     const SCHEMA: Option<&'static str> = Some("personal");
+///     const PRIMARY_KEY: &'static [&'static str] = &["username"];
+///     const COLUMNS: &'static [&'static str] = &["username", "status"];
+/// }
+///
+/// let client = Postgrest::new("https://your.postgrest.endpoint");
+/// let parts = client.table::<User>().to_request_parts();
+/// assert_eq!(
+///     parts.url,
+///     "https://your.postgrest.endpoint/users?select=username%2Cstatus"
+/// );
+/// ```
+pub trait Table {
+    /// The name of the table or view.
+    const NAME: &'static str;
+    /// The schema, or `None` to use the client's.
+    const SCHEMA: Option<&'static str>;
+    /// The primary key columns.
+    const PRIMARY_KEY: &'static [&'static str];
+    /// The columns the type maps, in field order.
+    const COLUMNS: &'static [&'static str];
+}
+
+/// Renders `columns` as a `select` list, quoting names PostgREST would
+/// otherwise misparse.
+pub(crate) fn select_list(columns: &[&str]) -> String {
+    columns
+        .iter()
+        .map(|column| {
+            if column.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                column.to_string()
+            } else {
+                format!("\"{}\"", column.replace('"', "\"\""))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_special_columns() {
+        assert_eq!(
+            select_list(&["id", "first name", "crème", "a,b"]),
+            r#"id,"first name",crème,"a,b""#
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use http::{HeaderMap, StatusCode};
+use tracing::Span;
+
+// Parameters whose values describe the shape of the query rather than the
+// data being filtered on.
+const STRUCTURAL_KEYS: &[&str] = &[
+    "select",
+    "order",
+    "limit",
+    "offset",
+    "on_conflict",
+    "columns",
+];
+
+/// Renders `queries` as a readable (not percent-encoded) query string,
+/// optionally replacing filter values with `?` while keeping columns and
+/// operators.
+pub(crate) fn query_string(queries: &[(String, String)], redact: bool) -> String {
+    queries
+        .iter()
+        .map(|(key, value)| {
+            if redact && !is_structural(key) {
+                format!("{}={}", key, redact_filter(key, value))
+            } else {
+                format!("{}={}", key, value)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn is_structural(key: &str) -> bool {
+    let key = key.rsplit('.').next().unwrap_or(key);
+    STRUCTURAL_KEYS.contains(&key)
+}
+
+fn redact_filter(key: &str, value: &str) -> String {
+    let key = key.rsplit('.').next().unwrap_or(key);
+    if key == "and" || key == "or" {
+        return "(?)".to_string();
+    }
+    let (negation, rest) = match value.strip_prefix("not.") {
+        Some(rest) => ("not.", rest),
+        None => ("", value),
+    };
+    match rest.split_once('.') {
+        Some((operator, _)) => format!("{}{}.?", negation, operator),
+        None => "?".to_string(),
+    }
+}
+
+pub(crate) fn record_response(span: &Span, status: StatusCode, headers: &HeaderMap) {
+    span.record("status", status.as_u16());
+
+    // PostgREST reports its error code as `Proxy-Status: PostgREST; error=PGRST116`.
+    let error_code = headers
+        .get("Proxy-Status")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            value
+                .split(';')
+                .find_map(|p| p.trim().strip_prefix("error="))
+        });
+    if let Some(code) = error_code {
+        span.record("error_code", code);
+    }
+
+    // `Content-Range: 0-24/3573`, where the total is `*` unless counted.
+    let content_range = headers
+        .get("Content-Range")
+        .and_then(|value| value.to_str().ok());
+    if let Some(content_range) = content_range {
+        span.record("content_range", content_range);
+        let total = content_range
+            .rsplit_once('/')
+            .and_then(|(_, total)| total.parse::<u64>().ok());
+        if let Some(total) = total {
+            span.record("total", total);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{transport, Builder, RequestParts, SendFuture, Transport};
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    fn queries(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn keeps_values_unless_redacted() {
+        let queries = queries(&[("select", "*"), ("username", "eq.supabot")]);
+        assert_eq!(
+            query_string(&queries, false),
+            "select=*&username=eq.supabot"
+        );
+        assert_eq!(query_string(&queries, true), "select=*&username=eq.?");
+    }
+
+    #[test]
+    fn redacts_filter_values_only() {
+        let queries = queries(&[
+            ("cities.order", "name.asc"),
+            ("age", "not.gte.20"),
+            ("phrase", "fts(english).fat cats"),
+            ("or", "(id.eq.1,id.eq.2)"),
+        ]);
+        assert_eq!(
+            query_string(&queries, true),
+            "cities.order=name.asc&age=not.gte.?&phrase=fts(english).?&or=(?)"
+        );
+    }
+
+    /// Collects the fields recorded on any span.
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<HashMap<String, String>>>);
+
+    struct Fields<'a>(&'a mut HashMap<String, String>);
+
+    impl Visit for Fields<'_> {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            span.record(&mut Fields(&mut self.0.lock().unwrap()));
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _: &Id, values: &Record<'_>) {
+            values.record(&mut Fields(&mut self.0.lock().unwrap()));
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[derive(Clone)]
+    struct NotAcceptable;
+
+    impl Transport for NotAcceptable {
+        fn send(&self, _request: RequestParts) -> SendFuture<'_> {
+            let mut headers = HeaderMap::new();
+            headers.insert("Proxy-Status", "PostgREST; error=PGRST116".parse().unwrap());
+            headers.insert("Content-Range", "*/0".parse().unwrap());
+            let resp = transport::response(StatusCode::NOT_ACCEPTABLE, headers, "{}");
+            Box::pin(async move { Ok(resp) })
+        }
+    }
+
+    #[tokio::test]
+    async fn records_response_on_span() {
+        let capture = Capture::default();
+        let _guard = tracing::subscriber::set_default(capture.clone());
+        let url = "http://localhost:3000/users";
+        Builder::new(
+            url,
+            Some("personal".into()),
+            HeaderMap::new(),
+            NotAcceptable,
+        )
+        .eq("username", "supabot")
+        .single()
+        .exact_count()
+        .execute()
+        .await
+        .unwrap();
+
+        let fields = capture.0.lock().unwrap();
+        let field = |name: &str| fields.get(name).map(String::as_str);
+        assert_eq!(field("table"), Some("users"));
+        assert_eq!(field("function"), None);
+        assert_eq!(field("method"), Some("GET"));
+        assert_eq!(field("schema"), Some("personal"));
+        assert_eq!(field("query"), Some("username=eq.supabot"));
+        assert_eq!(field("status"), Some("406"));
+        assert_eq!(field("error_code"), Some("PGRST116"));
+        assert_eq!(field("content_range"), Some("*/0"));
+        assert_eq!(field("total"), Some("0"));
+        assert_eq!(field("error"), None);
+    }
+}
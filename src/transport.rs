@@ -0,0 +1,212 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures_util::{stream, Stream, TryStreamExt};
+use http::{HeaderMap, StatusCode};
+use serde::de::DeserializeOwned;
+
+use crate::{Error, RequestParts};
+
+/// The future returned by [`Transport::send`].
+pub type SendFuture<'a> = Pin<Box<dyn Future<Output = Result<Response, Error>> + Send + 'a>>;
+
+/// The future returned by [`Transport::sleep`].
+pub type SleepFuture<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+
+/// Sends the requests built by a [`Builder`](crate::Builder).
+///
+/// `reqwest::Client` is the default transport, with the default `reqwest`
+/// feature. Implement this trait to send requests through another HTTP
+/// stack, a test double, or an in-process PostgREST, and pass it to
+/// [`Postgrest::with_transport`](crate::Postgrest::with_transport).
+/// Responses are built with [`response`] or [`Response::from_stream`].
+///
+/// Retries wait with [`sleep`](Self::sleep), which uses tokio's timer by
+/// default. Without the `reqwest` feature there is no default, so every
+/// transport provides a timer for its executor.
+///
+/// # Example
+///
+/// ```
+/// use postgrest::{transport, Postgrest, RequestParts, SendFuture, Transport};
+/// use http::{HeaderMap, StatusCode};
+///
+/// #[derive(Clone)]
+/// struct AlwaysEmpty;
+///
+/// impl Transport for AlwaysEmpty {
+///     fn send(&self, _request: RequestParts) -> SendFuture<'_> {
+///         Box::pin(async { Ok(transport::response(StatusCode::OK, HeaderMap::new(), "[]")) })
+///     }
+/// }
+///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = Postgrest::with_transport("http://localhost:3000", AlwaysEmpty);
+/// let resp = client.from("users").select("*").execute().await?;
+/// assert_eq!(resp.text().await?, "[]");
+/// # Ok(())
+/// # }
+/// ```
+pub trait Transport: Send + Sync {
+    /// Sends `request` and returns the response status, headers and body.
+    fn send(&self, request: RequestParts) -> SendFuture<'_>;
+
+    /// Waits `duration` before a retry. Requires a tokio runtime unless
+    /// overridden.
+    #[cfg(feature = "reqwest")]
+    fn sleep(&self, duration: Duration) -> SleepFuture<'_> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+
+    /// Waits `duration` before a retry.
+    #[cfg(not(feature = "reqwest"))]
+    fn sleep(&self, duration: Duration) -> SleepFuture<'_>;
+}
+
+#[cfg(feature = "reqwest")]
+impl Transport for reqwest::Client {
+    fn send(&self, request: RequestParts) -> SendFuture<'_> {
+        let request = request.into_request(self);
+        Box::pin(async move {
+            let resp = request.send().await?;
+            let status = resp.status();
+            let headers = resp.headers().clone();
+            let body = resp.bytes_stream().map_err(Error::from);
+            Ok(Response::from_stream(status, headers, body))
+        })
+    }
+}
+
+impl<T> Transport for Arc<T>
+where
+    T: Transport + ?Sized,
+{
+    fn send(&self, request: RequestParts) -> SendFuture<'_> {
+        (**self).send(request)
+    }
+
+    fn sleep(&self, duration: Duration) -> SleepFuture<'_> {
+        (**self).sleep(duration)
+    }
+}
+
+impl<T> Transport for Box<T>
+where
+    T: Transport + ?Sized,
+{
+    fn send(&self, request: RequestParts) -> SendFuture<'_> {
+        (**self).send(request)
+    }
+
+    fn sleep(&self, duration: Duration) -> SleepFuture<'_> {
+        (**self).sleep(duration)
+    }
+}
+
+type Body = Pin<Box<dyn Stream<Item = Result<Bytes, Error>> + Send>>;
+
+/// The response to a request: its status, headers and a body that is read
+/// as it arrives.
+pub struct Response {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Body,
+}
+
+impl Response {
+    /// Creates a response with a body that is already in memory.
+    pub fn new<B>(status: StatusCode, headers: HeaderMap, body: B) -> Self
+    where
+        B: Into<Bytes>,
+    {
+        let body = body.into();
+        Self::from_stream(status, headers, stream::once(async move { Ok(body) }))
+    }
+
+    /// Creates a response with a body that is read chunk by chunk.
+    pub fn from_stream<S>(status: StatusCode, headers: HeaderMap, body: S) -> Self
+    where
+        S: Stream<Item = Result<Bytes, Error>> + Send + 'static,
+    {
+        Response {
+            status,
+            headers,
+            body: Box::pin(body),
+        }
+    }
+
+    /// The response status.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// The response headers.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Reads the whole body.
+    pub async fn bytes(self) -> Result<Bytes, Error> {
+        let chunks: Vec<Bytes> = self.body.try_collect().await?;
+        Ok(match <[Bytes; 1]>::try_from(chunks) {
+            Ok([chunk]) => chunk,
+            Err(chunks) => chunks.concat().into(),
+        })
+    }
+
+    /// Reads the whole body as text, replacing invalid UTF-8.
+    pub async fn text(self) -> Result<String, Error> {
+        let body = self.bytes().await?;
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+
+    /// Reads the whole body and deserializes it from JSON.
+    pub async fn json<T>(self) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(serde_json::from_slice(&self.bytes().await?)?)
+    }
+
+    /// The body chunks, as they arrive.
+    pub fn bytes_stream(self) -> impl Stream<Item = Result<Bytes, Error>> + Send + Unpin {
+        self.body
+    }
+}
+
+impl fmt::Debug for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Response")
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Builds the response of a custom [`Transport`] from its status, headers
+/// and body.
+pub fn response<B>(status: StatusCode, headers: HeaderMap, body: B) -> Response
+where
+    B: Into<Bytes>,
+{
+    Response::new(status, headers, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn builds_response() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Range", "0-0/1".parse().unwrap());
+        let resp = response(StatusCode::CREATED, headers, r#"[{"id":1}]"#);
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert_eq!(resp.headers().get("Content-Range").unwrap(), "0-0/1");
+        assert_eq!(resp.text().await.unwrap(), r#"[{"id":1}]"#);
+    }
+}
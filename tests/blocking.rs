@@ -0,0 +1,37 @@
+#![cfg(feature = "blocking")]
+
+use postgrest::blocking::Postgrest;
+
+use std::error::Error;
+
+const REST_URL: &str = "http://localhost:3000";
+
+#[test]
+fn basic_data() -> Result<(), Box<dyn Error>> {
+    let client = Postgrest::new(REST_URL);
+    let resp = client
+        .from("users")
+        .select("username")
+        .eq("status", "OFFLINE")
+        .execute()?;
+    let body = resp.text()?;
+    let body = json::parse(&body)?;
+
+    assert_eq!(body[0]["username"], "kiwicopple");
+
+    Ok(())
+}
+
+#[test]
+fn other_schema_rpc() -> Result<(), Box<dyn Error>> {
+    let client = Postgrest::new(REST_URL).schema("personal");
+    let resp = client
+        .rpc("get_status", r#"{"name_param": "leroyjenkins"}"#)
+        .execute()?;
+    let body = resp.text()?;
+    let body = json::parse(&body)?;
+
+    assert_eq!(body, "ONLINE");
+
+    Ok(())
+}
@@ -0,0 +1,101 @@
+#![cfg(feature = "derive")]
+
+use postgrest::{Postgrest, PostgrestTable, Table};
+use serde::Deserialize;
+
+const REST_URL: &str = "http://localhost:3000";
+
+#[derive(PostgrestTable)]
+#[postgrest(table = "users", schema = "personal")]
+#[allow(dead_code)]
+struct User {
+    #[postgrest(primary_key)]
+    username: String,
+    #[postgrest(rename = "age_range")]
+    age: String,
+    status: String,
+    #[postgrest(skip)]
+    cached: bool,
+}
+
+#[derive(PostgrestTable, Deserialize)]
+#[allow(dead_code)]
+struct ChannelMessage {
+    #[postgrest(primary_key)]
+    id: i64,
+    r#type: String,
+    #[serde(rename = "message text")]
+    message: String,
+    #[serde(skip)]
+    local: u8,
+}
+
+#[derive(PostgrestTable, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[allow(dead_code)]
+struct UserProfile {
+    user_name: String,
+    #[serde(rename = "AGE")]
+    age_range: String,
+    last_seen_at: Option<String>,
+}
+
+#[test]
+fn records_table_metadata() {
+    assert_eq!(User::NAME, "users");
+    assert_eq!(User::SCHEMA, Some("personal"));
+    assert_eq!(User::PRIMARY_KEY, ["username"]);
+    assert_eq!(User::COLUMNS, ["username", "age_range", "status"]);
+
+    assert_eq!(ChannelMessage::NAME, "channel_message");
+    assert_eq!(ChannelMessage::SCHEMA, None);
+    assert_eq!(ChannelMessage::COLUMNS, ["id", "type", "message text"]);
+}
+
+#[test]
+fn follows_serde_rename_all() {
+    assert_eq!(UserProfile::COLUMNS, ["userName", "AGE", "lastSeenAt"]);
+    assert_eq!(UserProfile::user_name.name(), "userName");
+    let profile: UserProfile =
+        serde_json::from_str(r#"{"userName": "supabot", "AGE": "[1,2)", "lastSeenAt": null}"#)
+            .unwrap();
+    assert_eq!(profile.user_name, "supabot");
+}
+
+#[test]
+fn table_selects_columns() {
+    let client = Postgrest::new(REST_URL);
+    let parts = client
+        .table::<User>()
+        .eq("status", "ONLINE")
+        .to_request_parts();
+    assert_eq!(
+        parts.url,
+        "http://localhost:3000/users?select=username%2Cage_range%2Cstatus&status=eq.ONLINE"
+    );
+    assert_eq!(parts.headers["Accept-Profile"], "personal");
+
+    let parts = client.table::<User>().select("status").to_request_parts();
+    assert_eq!(parts.url, "http://localhost:3000/users?select=status");
+
+    let parts = client.table::<ChannelMessage>().to_request_parts();
+    assert_eq!(
+        parts.url,
+        "http://localhost:3000/channel_message?select=id%2Ctype%2C%22message+text%22"
+    );
+}
+
+#[test]
+fn typed_columns_filter_and_order() {
+    let client = Postgrest::new(REST_URL);
+    let parts = client
+        .table::<ChannelMessage>()
+        .eq(ChannelMessage::id, 1)
+        .neq(ChannelMessage::r#type, "system")
+        .order(ChannelMessage::id.desc())
+        .to_request_parts();
+    assert!(parts
+        .url
+        .ends_with("&id=eq.1&type=neq.system&order=id.desc"));
+    assert_eq!(User::age.name(), "age_range");
+}
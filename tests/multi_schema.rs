@@ -150,3 +150,31 @@ async fn nonexisting_schema_for_rpc() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn read_other_schema_per_request() -> Result<(), Box<dyn Error>> {
+    let client = Postgrest::new(REST_URL);
+    let resp = client
+        .from_schema("personal", "users")
+        .select("username")
+        .eq("username", "leroyjenkins")
+        .execute()
+        .await?;
+    let body = resp.text().await?;
+    let body = json::parse(&body)?;
+
+    assert_eq!(body, array![{"username": "leroyjenkins"}]);
+
+    let resp = client
+        .from("users")
+        .select("username")
+        .eq("username", "leroyjenkins")
+        .execute()
+        .await?;
+    let body = resp.text().await?;
+    let body = json::parse(&body)?;
+
+    assert_eq!(body, array![]);
+
+    Ok(())
+}